@@ -1,10 +1,10 @@
-#version 330 core
 
 in vec2 uv;
+in vec4 text_col;
 out vec4 col;
 uniform sampler2D t;
 
 void main() {
   float val = texture(t, uv).r;
-  col = vec4(0.05, 0.05, 0.05, val);
+  col = vec4(text_col.rgb, text_col.a * val);
 }
@@ -1,28 +1,45 @@
-#version 430 core
 layout (location = 0) in vec2 p; // vertex coordinates in unit square
 layout (location = 1) in ivec4 cell_rect; // rect for the whole cell in pixels
 layout (location = 2) in int glyph; // index of the glyph in the atlas
+layout (location = 3) in vec3 colour; // text colour
+layout (location = 4) in vec2 centre; // centre of the rect of the text
+// scale and counterclockwise rotation around the centre, and opacity
+layout (location = 5) in vec3 transform;
 
 struct glyph_info_t {
   vec4 uv_rect;
   vec4 rect;
 };
 
+#ifdef STORAGE_BUFFER
 layout(std430, binding = 0) buffer atlas_t {
   glyph_info_t info[];
 } atlas;
+#else
+// must match GlyphCache::MAX_UNIFORM_GLYPHS
+#define MAX_GLYPHS 512
+layout(std140) uniform atlas_t {
+  glyph_info_t info[MAX_GLYPHS];
+} atlas;
+#endif
 
 uniform ivec4 viewport;
 out vec2 uv;
+out vec4 text_col;
 
 void main() {
   glyph_info_t info = atlas.info[glyph];
 
-  vec4 rect = vec4(info.rect.xy * cell_rect.zw, info.rect.zw * cell_rect.zw);
-  vec2 pos = cell_rect.xy + rect.xy + rect.zw * p;
+  vec2 cell_size = vec2(cell_rect.zw);
+  vec4 rect = vec4(info.rect.xy * cell_size, info.rect.zw * cell_size);
+  vec2 pos = vec2(cell_rect.xy) + rect.xy + rect.zw * p;
+  float c = cos(transform.y);
+  float s = sin(transform.y);
+  pos = centre + mat2(c, s, -s, c) * (pos - centre) * transform.x;
   uv = info.uv_rect.xy + info.uv_rect.zw * p;
+  text_col = vec4(colour, transform.z);
 
   gl_Position = vec4(
-    (pos - viewport.xy) * 2 / viewport.zw - vec2(1.0, 1.0),
+    (pos - vec2(viewport.xy)) * 2.0 / vec2(viewport.zw) - vec2(1.0, 1.0),
     0.0, 1.0);
 }
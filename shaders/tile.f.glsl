@@ -1,7 +1,6 @@
-#version 330 core
 
 in vec2 uv;
-in vec3 frag_col;
+in vec4 frag_col;
 
 out vec4 col;
 
@@ -17,9 +16,9 @@ void main() {
   vec2 dim = vec2(0.5, 0.5);
   if (radius > 0.0) {
     float val = radius - sdf(dim, dim - radius * vec2(1.0, 1.0), uv);
-    col = vec4(frag_col, smoothstep(-0.005, 0.005, val * alpha));
+    col = vec4(frag_col.rgb, frag_col.a * smoothstep(-0.005, 0.005, val * alpha));
   }
   else {
-    col = vec4(frag_col, alpha);
+    col = vec4(frag_col.rgb, frag_col.a * alpha);
   }
 }
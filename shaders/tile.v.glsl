@@ -1,18 +1,24 @@
-#version 330 core
-layout (location = 0) in vec2 p;
-layout (location = 1) in ivec2 pos;
-layout (location = 2) in vec3 colour;
+layout (location = 0) in vec2 p; // vertex coordinates in unit square
+layout (location = 1) in ivec4 rect; // rect of the tile in pixels
+layout (location = 2) in vec4 colour;
+// scale and counterclockwise rotation around the centre, and opacity
+layout (location = 3) in vec3 transform;
 
-out vec3 frag_col;
+out vec4 frag_col;
 out vec2 uv;
 uniform ivec4 viewport;
 
 void main() {
-  frag_col = colour;
+  frag_col = vec4(colour.rgb, colour.a * transform.z);
 
   uv = p;
 
+  vec2 size = vec2(rect.zw);
+  vec2 centre = vec2(rect.xy) + size * 0.5;
+  float c = cos(transform.y);
+  float s = sin(transform.y);
+  vec2 pos = centre + mat2(c, s, -s, c) * (size * (p - 0.5)) * transform.x;
   gl_Position = vec4(
-    (vec2(pos) - viewport.xy) * 2 / viewport.zw - vec2(1.0, 1.0),
+    (pos - vec2(viewport.xy)) * 2.0 / vec2(viewport.zw) - vec2(1.0, 1.0),
     0.0, 1.0);
 }
@@ -1,39 +1,327 @@
+use crate::clock::Clock;
+use crate::config::Config;
 use crate::game::Game;
+use serde::Deserialize;
+use std::f32::consts::PI;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
-#[derive(Debug)]
-pub struct Animation<T> {
-    start: Instant,
-    duration: Duration,
-    pub inner: T,
-    pub result: Game,
+/// Easing curve, mapping linear time in `[0, 1]` to animation progress.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Easing {
+    Linear,
+    /// Symmetric sigmoid, slow at both ends.
+    #[default]
+    Sigmoid,
+    /// Cubic ease in and out.
+    Cubic,
+    /// Overshoots the target slightly before settling.
+    Back,
+    /// Oscillates around the target with decreasing amplitude.
+    Elastic,
+    /// Damped spring.
+    Spring,
+}
+
+const SIGMOID_PARAMETER: f32 = 2.5;
+const BACK_PARAMETER: f32 = 1.70158;
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        if t >= 1.0 {
+            return 1.0;
+        }
+        match self {
+            Easing::Linear => t,
+            Easing::Sigmoid => {
+                let tp0 = t.powf(SIGMOID_PARAMETER);
+                let tp1 = (1.0 - t).powf(SIGMOID_PARAMETER);
+                tp0 / (tp0 + tp1)
+            }
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+            Easing::Back => {
+                let u = t - 1.0;
+                1.0 + (BACK_PARAMETER + 1.0) * u * u * u + BACK_PARAMETER * u * u
+            }
+            Easing::Elastic => {
+                2.0_f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * 2.0 * PI / 3.0).sin() + 1.0
+            }
+            Easing::Spring => 1.0 - (-6.0 * t).exp() * (2.5 * PI * t).cos(),
+        }
+    }
+}
+
+/// Configuration of a single animation track.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TrackConfig {
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub easing: Easing,
 }
 
 pub fn default_duration_ms() -> u64 {
     160
 }
 
-const EASE_PARAMETER: f32 = 2.5;
+pub fn default_merge() -> TrackConfig {
+    TrackConfig {
+        duration_ms: 100,
+        easing: Easing::Linear,
+    }
+}
+
+pub fn default_spawn() -> TrackConfig {
+    TrackConfig {
+        duration_ms: 120,
+        easing: Easing::Back,
+    }
+}
+
+pub fn default_game_over() -> TrackConfig {
+    TrackConfig {
+        duration_ms: 300,
+        easing: Easing::Linear,
+    }
+}
+
+/// Slowest animation speed, to keep durations finite.
+const MIN_SPEED: f32 = 0.01;
+
+pub fn default_speed() -> f32 {
+    1.0
+}
+
+/// A segment of the timeline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Track {
+    pub delay: Duration,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Track {
+    fn new(delay: Duration, config: TrackConfig, speed: f32) -> Self {
+        Self {
+            delay,
+            duration: Duration::from_nanos(
+                (config.duration_ms as f64 * 1e6 / speed as f64).round() as u64,
+            ),
+            easing: config.easing,
+        }
+    }
+
+    fn end(&self) -> Duration {
+        self.delay + self.duration
+    }
+
+    /// Eased progress of the track at the given time since the start of the
+    /// timeline.
+    fn at(&self, elapsed: Duration) -> f32 {
+        if elapsed >= self.end() {
+            return 1.0;
+        }
+        let t = elapsed.saturating_sub(self.delay).as_secs_f32() / self.duration.as_secs_f32();
+        self.easing.apply(t)
+    }
+}
+
+/// Progress of each track of an animation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// Whether tiles are still sliding to their new positions.
+    pub sliding: bool,
+    pub slide: f32,
+    pub merge: f32,
+    pub spawn: f32,
+    pub game_over: f32,
+}
+
+impl Progress {
+    pub const DONE: Progress = Progress {
+        sliding: false,
+        slide: 1.0,
+        merge: 1.0,
+        spawn: 1.0,
+        game_over: 1.0,
+    };
+}
+
+/// Timing of the animations of a single turn: tiles slide first, then merged
+/// tiles pulse and new tiles pop in, while the game over screen fades in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timeline {
+    pub slide: Track,
+    pub merge: Track,
+    pub spawn: Track,
+    pub game_over: Track,
+}
 
-impl<T> Animation<T> {
-    pub fn new(duration: Duration, inner: T, result: Game) -> Self {
+impl Timeline {
+    pub fn from_config(config: &Config) -> Self {
+        let speed = config.animation_speed.max(MIN_SPEED);
+        let slide = Track::new(
+            Duration::ZERO,
+            TrackConfig {
+                duration_ms: config.animation_duration_ms,
+                easing: config.easing,
+            },
+            speed,
+        );
         Self {
-            start: Instant::now(),
-            duration,
-            inner,
+            slide,
+            merge: Track::new(slide.end(), config.merge_animation, speed),
+            spawn: Track::new(slide.end(), config.spawn_animation, speed),
+            game_over: Track::new(slide.end(), config.game_over_animation, speed),
+        }
+    }
+
+    pub fn progress(&self, elapsed: Duration) -> Progress {
+        Progress {
+            sliding: elapsed < self.slide.end(),
+            slide: self.slide.at(elapsed),
+            merge: self.merge.at(elapsed),
+            spawn: self.spawn.at(elapsed),
+            game_over: self.game_over.at(elapsed),
+        }
+    }
+
+    /// Total duration of the timeline, with or without the game over track.
+    pub fn duration(&self, game_over: bool) -> Duration {
+        let mut end = self.slide.end().max(self.merge.end()).max(self.spawn.end());
+        if game_over {
+            end = end.max(self.game_over.end());
+        }
+        end
+    }
+}
+
+#[derive(Debug)]
+pub struct Animation {
+    clock: Rc<dyn Clock>,
+    start: Instant,
+    timeline: Timeline,
+    duration: Duration,
+    pub result: Game,
+}
+
+impl Animation {
+    pub fn new(clock: Rc<dyn Clock>, timeline: Timeline, result: Game) -> Self {
+        Self {
+            start: clock.now(),
+            clock,
+            timeline,
+            duration: timeline.duration(result.is_over()),
             result,
         }
     }
 
-    pub fn time(&self) -> f32 {
-        let now = Instant::now();
-        let t = (now - self.start).as_nanos() as f32 / self.duration.as_nanos() as f32;
-        if t >= 0.0 && t <= 1.0 {
-            let tp0 = t.powf(EASE_PARAMETER);
-            let tp1 = (1.0 - t).powf(EASE_PARAMETER);
-            tp0 / (tp0 + tp1)
-        } else {
-            t
+    fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.start)
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.timeline.progress(self.elapsed())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed() >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    const ALL: [Easing; 6] = [
+        Easing::Linear,
+        Easing::Sigmoid,
+        Easing::Cubic,
+        Easing::Back,
+        Easing::Elastic,
+        Easing::Spring,
+    ];
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in ALL {
+            assert_eq!(0.0, easing.apply(0.0), "{:?}", easing);
+            assert_eq!(1.0, easing.apply(1.0), "{:?}", easing);
+            assert_eq!(1.0, easing.apply(1.5), "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easing_midpoint() {
+        assert_eq!(0.5, Easing::Linear.apply(0.5));
+        assert!((Easing::Sigmoid.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!((Easing::Cubic.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!(Easing::Back.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn timeline() {
+        let timeline = Timeline::from_config(&Config::default());
+        let slide = timeline.slide.duration;
+
+        let p = timeline.progress(Duration::ZERO);
+        assert!(p.sliding);
+        assert_eq!(0.0, p.slide);
+        assert_eq!(0.0, p.spawn);
+
+        let p = timeline.progress(slide);
+        assert!(!p.sliding);
+        assert_eq!(1.0, p.slide);
+        assert_eq!(0.0, p.merge);
+
+        assert_eq!(Progress::DONE, timeline.progress(timeline.duration(true)));
+        assert!(timeline.duration(false) < timeline.duration(true));
+    }
+
+    #[test]
+    fn speed() {
+        let normal = Timeline::from_config(&Config::default());
+        let fast = Timeline::from_config(&Config {
+            animation_speed: 2.0,
+            ..Config::default()
+        });
+        assert_eq!(normal.slide.duration / 2, fast.slide.duration);
+        assert_eq!(normal.duration(true) / 2, fast.duration(true));
+    }
+
+    #[test]
+    fn frame_stepping() {
+        let clock = Rc::new(ManualClock::new());
+        let config = Config {
+            easing: Easing::Linear,
+            ..Config::default()
+        };
+        let timeline = Timeline::from_config(&config);
+        let anim = Animation::new(clock.clone(), timeline, Game::new(4, 4));
+
+        assert_eq!(0.0, anim.progress().slide);
+        clock.advance(timeline.slide.duration / 4);
+        assert_eq!(0.25, anim.progress().slide);
+        assert!(!anim.is_finished());
+
+        let mut frames = 0;
+        while !anim.is_finished() {
+            clock.step_frame(std::num::NonZeroU32::new(50).unwrap());
+            frames += 1;
         }
+        let p = anim.progress();
+        assert_eq!((1.0, 1.0, 1.0), (p.slide, p.merge, p.spawn));
+        let remaining = timeline.duration(false) - timeline.slide.duration / 4;
+        assert_eq!(remaining.as_millis() / 20, frames);
     }
 }
@@ -0,0 +1,272 @@
+//! Terminal frontend: the same game, configuration and key bindings as the
+//! window, drawn with ANSI true colour, e.g. to play over SSH.
+
+use anyhow::Context;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, ExecutableCommand, QueueableCommand};
+use iron::animation::Progress;
+use iron::clock::SystemClock;
+use iron::config::{self, Config};
+use iron::display_list::{self, Frame, Overlay};
+use iron::keys::{Action, Key};
+use iron::layout::Layout;
+use iron::session::Session;
+use iron::state::{Control, Prompt};
+use iron::stats;
+use iron::theme::Theme;
+use iron::tui::{self, Screen};
+use iron::ui::{Kind, Status, Widget};
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Time between frames while tiles are moving. Terminals, especially over
+/// SSH, only show a few frames of each move.
+const FRAME_INTERVAL: Duration = Duration::from_millis(40);
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it
+/// when dropped.
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        io::stdout()
+            .execute(EnterAlternateScreen)?
+            .execute(cursor::Hide)?;
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = out.execute(cursor::Show);
+        let _ = out.execute(LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct App {
+    config: Config,
+    theme: Theme,
+    session: Session,
+    prompt: Option<Prompt>,
+    paused: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new(config: Config, theme: Theme) -> Self {
+        App {
+            session: Session::new(&config, Rc::new(SystemClock), stats::load_or_default()),
+            config,
+            theme,
+            prompt: None,
+            paused: false,
+            quit: false,
+        }
+    }
+
+    /// Show errors, e.g. from saving the statistics, in place of the board.
+    fn report(&mut self, result: anyhow::Result<()>) {
+        if let Err(e) = result {
+            self.prompt = Some(Prompt::Message(format!("{:#}", e)));
+        }
+    }
+
+    fn new_game(&mut self) {
+        let result = self.session.new_game(&self.config);
+        self.report(result);
+    }
+
+    fn action(&mut self, action: Action) {
+        if let Some(dir) = action.direction() {
+            let result = self.session.input(dir, &self.config);
+            self.report(result);
+            return;
+        }
+
+        match action {
+            Action::Quit => self.exit(),
+            Action::Pause => {
+                self.paused = true;
+                self.session.timer.stop(Instant::now());
+            }
+            Action::NewGame => {
+                if self.session.latest().is_over() {
+                    self.new_game();
+                } else {
+                    self.prompt = Some(Prompt::NewGame);
+                }
+            }
+            Action::Undo => {
+                self.session.undo();
+            }
+            Action::Hint => self.prompt = self.session.latest().hint().map(Prompt::Hint),
+            Action::Save => {
+                self.prompt = Some(Prompt::Message(match self.session.save() {
+                    Ok(()) => "Game saved".to_string(),
+                    Err(e) => format!("Save failed: {:#}", e),
+                }));
+            }
+            Action::Load => {
+                if let Err(e) = self.session.load() {
+                    self.prompt = Some(Prompt::Message(format!("Load failed: {:#}", e)));
+                }
+            }
+            Action::Screenshot => {
+                self.prompt = Some(Prompt::Message(
+                    "Screenshots are only taken in the window".to_string(),
+                ))
+            }
+            Action::Left | Action::Right | Action::Up | Action::Down => {}
+        }
+    }
+
+    fn key(&mut self, key: Key) {
+        let action = self.config.keys.action(key);
+        if self.paused {
+            // any key resumes the game
+            self.paused = false;
+            if !self.session.latest().is_over() {
+                self.session.timer.resume(Instant::now());
+            }
+            if action == Some(Action::Quit) {
+                self.exit();
+            }
+        } else if let Some(Prompt::NewGame) = self.prompt.take() {
+            if let Key::Character('y' | 'Y' | '\r' | ' ') = key {
+                self.new_game();
+            }
+        } else if let Some(action) = action {
+            self.action(action);
+        }
+    }
+
+    fn exit(&mut self) {
+        let result = self.session.record_score();
+        self.report(result);
+        self.quit = true;
+    }
+
+    /// Screen to show over the board, if any.
+    fn overlay(&self) -> Option<Overlay> {
+        if self.paused {
+            return Some(Overlay::message("Paused"));
+        }
+        match &self.prompt {
+            Some(prompt) => Some(Overlay::message(prompt.text())),
+            None if self.session.latest().is_over() => Some(Overlay::message("Game over")),
+            None => None,
+        }
+    }
+
+    /// Draw the current frame on a terminal of the given size.
+    fn draw(&self, (cols, rows): (u16, u16)) -> Screen {
+        let (width, height) = tui::pixel_size(cols, rows);
+        let game = self.session.latest();
+        let mut layout = Layout::compute(width, height, game.width(), game.height());
+        // tiles are only a few pixels wide, keep them apart
+        if layout.unit >= 4 {
+            layout.gap = layout.gap.max(1);
+        }
+
+        // the panel only shows values, there is no mouse to press buttons
+        let entries = self.session.hud().entries();
+        let widgets = layout
+            .panel_cells(entries.len())
+            .into_iter()
+            .zip(entries)
+            .map(|(rect, (label, value))| {
+                Widget::new(Control::Text, rect, label, Kind::Value(value))
+            })
+            .collect::<Vec<_>>();
+
+        let (target, progress) = match &self.session.animation {
+            Some(a) => (Some(&a.result), a.progress()),
+            None => (None, Progress::DONE),
+        };
+        let overlay = self.overlay();
+        let list = display_list::build(&Frame {
+            layout: &layout,
+            theme: &self.theme,
+            radius: self.config.tile_radius,
+            game: &self.session.game,
+            target,
+            progress,
+            overlay: overlay.as_ref(),
+            widgets: widgets.iter().map(|w| (w, Status::Normal, false)).collect(),
+        });
+        Screen::draw(&list, cols, rows)
+    }
+
+    /// How long to wait for input before drawing the next frame.
+    fn timeout(&self) -> Duration {
+        if self.session.animation.is_some() {
+            FRAME_INTERVAL
+        } else if self.session.timer.running() {
+            // wake up when the timer shown in the panel ticks
+            let elapsed = self.session.timer.elapsed(Instant::now());
+            Duration::from_secs(1) - Duration::from_nanos(elapsed.subsec_nanos() as u64)
+        } else {
+            Duration::from_secs(60)
+        }
+    }
+}
+
+fn run(app: &mut App) -> anyhow::Result<()> {
+    let _terminal = Terminal::enter().context("could not set up the terminal")?;
+    let mut out = BufWriter::new(io::stdout());
+    let mut size = terminal::size().context("could not get the size of the terminal")?;
+    let mut previous: Option<Screen> = None;
+
+    while !app.quit {
+        let screen = app.draw(size);
+        screen.write(&mut out, previous.as_ref())?;
+        previous = Some(screen);
+
+        if event::poll(app.timeout())? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    // raw mode turns Ctrl-C into a key press
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        app.exit();
+                    } else if let Some(key) = Key::from_terminal(key.code) {
+                        app.key(key);
+                    }
+                }
+                Event::Resize(cols, rows) => {
+                    size = (cols, rows);
+                    previous = None;
+                    out.queue(Clear(ClearType::All))?;
+                }
+                _ => {}
+            }
+        }
+        let result = app.session.tick(&app.config);
+        app.report(result);
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
+    let theme = Theme::load(&config.theme).unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
+
+    let mut app = App::new(config, theme);
+    if let Err(e) = run(&mut app) {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::image::parse_size;
+use crate::movie;
+use anyhow::{bail, Context};
+use std::ops::Range;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+usage: iron [options]
+
+options:
+  --thumbnail <file>   render the saved game to a PNG file and exit
+  --export <file>      render the saved game without OpenGL to a PNG or SVG
+                       file and exit
+  --screenshot <file>  open the window on the saved game, save its first
+                       frame to a PNG file and exit
+  --replay <file>      render a replay to an animated GIF, or to numbered PNG
+                       files in a directory, and exit
+  --output <path>      where to render the replay (default: replay.gif)
+  --fps <n>            frame rate of the rendered replay (default: 30)
+  --speed <x>          speed of the rendered replay, from 0.01 to 100
+                       (default: 1)
+  --turns <a-b>        only render moves a to b of the replay, e.g. 10-20,
+                       10- or -20
+  --size <WxH>         size of the window and of rendered images
+                       (default: 800x600)
+  --theme <name>       theme to draw with, instead of the configured one
+  --help               show this message";
+
+/// Command line arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Args {
+    /// Where to render the saved game, instead of opening a window.
+    pub thumbnail: Option<PathBuf>,
+    /// Where to render the saved game with the software renderer.
+    pub export: Option<PathBuf>,
+    /// Where to save the first frame drawn in the window.
+    pub screenshot: Option<PathBuf>,
+    /// Replay to render, instead of opening a window.
+    pub replay: Option<PathBuf>,
+    /// Where to render the replay.
+    pub output: PathBuf,
+    pub fps: u32,
+    pub speed: f32,
+    /// Turns of the replay to render, counted from 0.
+    pub turns: Range<usize>,
+    /// Size of the window and of rendered images.
+    pub size: (u32, u32),
+    pub theme: Option<String>,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            thumbnail: None,
+            export: None,
+            screenshot: None,
+            replay: None,
+            output: PathBuf::from("replay.gif"),
+            fps: 30,
+            speed: 1.0,
+            turns: 0..usize::MAX,
+            size: crate::INITIAL_SIZE,
+            theme: None,
+            help: false,
+        }
+    }
+}
+
+/// Parse arguments, without the program name.
+pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--thumbnail" => parsed.thumbnail = Some(value()?.into()),
+            "--export" => parsed.export = Some(value()?.into()),
+            "--screenshot" => parsed.screenshot = Some(value()?.into()),
+            "--replay" => parsed.replay = Some(value()?.into()),
+            "--output" => parsed.output = value()?.into(),
+            "--fps" => parsed.fps = parse_positive(&arg, &value()?)?,
+            "--speed" => parsed.speed = parse_speed(&value()?)?,
+            "--turns" => parsed.turns = parse_turns(&value()?)?,
+            "--size" => parsed.size = parse_size(&value()?)?,
+            "--theme" => parsed.theme = Some(value()?),
+            "-h" | "--help" => parsed.help = true,
+            _ => bail!("unknown argument {:?}\n\n{}", arg, USAGE),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_positive<T: std::str::FromStr + PartialOrd + Default>(
+    arg: &str,
+    s: &str,
+) -> anyhow::Result<T> {
+    match s.parse() {
+        Ok(v) if v > T::default() => Ok(v),
+        _ => bail!(
+            "invalid value {:?} for {}, expected a positive number",
+            s,
+            arg
+        ),
+    }
+}
+
+/// Parse a replay speed, which has to be a finite number in `movie::SPEEDS`.
+fn parse_speed(s: &str) -> anyhow::Result<f32> {
+    match s.parse() {
+        Ok(v) if movie::SPEEDS.contains(&v) => Ok(v),
+        _ => bail!(
+            "invalid value {:?} for --speed, expected a number from {} to {}",
+            s,
+            movie::SPEEDS.start(),
+            movie::SPEEDS.end()
+        ),
+    }
+}
+
+/// Parse a range of moves `a-b`, where either end may be left out, into the
+/// range of turns counted from 0.
+fn parse_turns(s: &str) -> anyhow::Result<Range<usize>> {
+    let parse = || -> Option<Range<usize>> {
+        let (a, b) = s.split_once('-').unwrap_or((s, s));
+        let first = if a.is_empty() { 1 } else { a.parse().ok()? };
+        let last = if b.is_empty() {
+            usize::MAX
+        } else {
+            b.parse().ok()?
+        };
+        (first > 0 && first <= last).then(|| first - 1..last)
+    };
+    parse().with_context(|| format!("invalid moves {:?}, expected e.g. 10-20", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> anyhow::Result<Args> {
+        parse(s.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(Args::default(), args("").unwrap());
+        let parsed = args("--size 64x48 --thumbnail out.png").unwrap();
+        assert_eq!(Some(PathBuf::from("out.png")), parsed.thumbnail);
+        assert_eq!((64, 48), parsed.size);
+        assert_eq!(
+            Some(PathBuf::from("board.svg")),
+            args("--export board.svg").unwrap().export
+        );
+        assert_eq!(
+            Some(PathBuf::from("shot.png")),
+            args("--screenshot shot.png").unwrap().screenshot
+        );
+        let parsed =
+            args("--replay run.toml --output frames --fps 50 --speed 2 --turns 3-").unwrap();
+        assert_eq!(Some(PathBuf::from("run.toml")), parsed.replay);
+        assert_eq!(PathBuf::from("frames"), parsed.output);
+        assert_eq!((50, 2.0), (parsed.fps, parsed.speed));
+        assert_eq!(2..usize::MAX, parsed.turns);
+        assert!(args("--fps 0").is_err());
+        assert!(args("--speed -1").is_err());
+        for speed in ["inf", "NaN", "1e30", "0.001", "101"] {
+            assert!(args(&format!("--speed {}", speed)).is_err(), "{}", speed);
+        }
+        assert_eq!(0.01, args("--speed 0.01").unwrap().speed);
+        assert_eq!(100.0, args("--speed 100").unwrap().speed);
+        assert!(args("--size").is_err());
+        assert!(args("--fullscreen").is_err());
+    }
+
+    #[test]
+    fn turns() {
+        assert_eq!(9..20, parse_turns("10-20").unwrap());
+        assert_eq!(0..20, parse_turns("-20").unwrap());
+        assert_eq!(9..usize::MAX, parse_turns("10-").unwrap());
+        assert_eq!(4..5, parse_turns("5").unwrap());
+        assert!(parse_turns("0-3").is_err());
+        assert!(parse_turns("5-4").is_err());
+        assert!(parse_turns("a-b").is_err());
+    }
+}
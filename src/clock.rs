@@ -0,0 +1,77 @@
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+/// Source of time for animations and the render loop.
+pub trait Clock: Debug {
+    fn now(&self) -> Instant;
+}
+
+/// The real time clock.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when explicitly advanced, for tests and
+/// frame-exact offline rendering.
+#[derive(Debug)]
+pub struct ManualClock {
+    base: Instant,
+    elapsed: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            elapsed: Cell::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, step: Duration) {
+        self.elapsed.set(self.elapsed.get() + step);
+    }
+
+    /// Advance by a single frame at the given frame rate.
+    pub fn step_frame(&self, fps: NonZeroU32) {
+        self.advance(Duration::from_secs(1) / fps.get());
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + self.elapsed.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual() {
+        let clock = ManualClock::new();
+        let t0 = clock.now();
+        assert_eq!(t0, clock.now());
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(Duration::from_millis(10), clock.now() - t0);
+
+        for _ in 0..50 {
+            clock.step_frame(NonZeroU32::new(50).unwrap());
+        }
+        assert_eq!(Duration::from_millis(1010), clock.now() - t0);
+    }
+}
@@ -1,13 +1,37 @@
-use crate::animation;
-use crate::tiles;
+use crate::animation::{self, Easing, TrackConfig};
+use crate::display_list;
+use crate::gesture;
+use crate::input;
+use crate::keys::KeyBindings;
+use crate::theme;
+use anyhow::Context;
 use serde::Deserialize;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
 
 #[derive(Deserialize)]
 pub struct Config {
     #[serde(default = "animation::default_duration_ms")]
     pub animation_duration_ms: u64,
 
-    #[serde(default = "tiles::default_tile_radius")]
+    /// Easing curve of sliding tiles.
+    #[serde(default)]
+    pub easing: Easing,
+
+    #[serde(default = "animation::default_merge")]
+    pub merge_animation: TrackConfig,
+
+    #[serde(default = "animation::default_spawn")]
+    pub spawn_animation: TrackConfig,
+
+    #[serde(default = "animation::default_game_over")]
+    pub game_over_animation: TrackConfig,
+
+    /// Factor applied to the speed of all animations.
+    #[serde(default = "animation::default_speed")]
+    pub animation_speed: f32,
+
+    #[serde(default = "display_list::default_tile_radius")]
     pub tile_radius: f32,
 
     #[serde(default = "default_size")]
@@ -15,15 +39,62 @@ pub struct Config {
 
     #[serde(default = "default_size")]
     pub height: usize,
+
+    /// Maximum number of moves to remember while an animation is running.
+    #[serde(default = "input::default_queue_size")]
+    pub input_queue_size: usize,
+
+    /// Finish the running animation immediately when a new move is entered,
+    /// instead of waiting for it to complete.
+    #[serde(default)]
+    pub snap_animations: bool,
+
+    /// Advance animations by exactly one frame at this rate for every frame
+    /// rendered, instead of following the real time.
+    #[serde(default)]
+    pub frame_rate: Option<NonZeroU32>,
+
+    /// Scale factor for the distance and speed needed to register a swipe.
+    #[serde(default = "gesture::default_sensitivity")]
+    pub swipe_sensitivity: f32,
+
+    /// Turn trackpad scroll gestures into moves.
+    #[serde(default)]
+    pub scroll_gestures: bool,
+
+    #[serde(default)]
+    pub keys: KeyBindings,
+
+    /// Name of a built-in theme, or path to a theme file.
+    #[serde(default = "theme::default_theme")]
+    pub theme: String,
+
+    /// Path of a font file, or family name of an installed font. The bundled
+    /// font is used if not set.
+    #[serde(default)]
+    pub font: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             animation_duration_ms: animation::default_duration_ms(),
-            tile_radius: tiles::default_tile_radius(),
+            easing: Easing::default(),
+            merge_animation: animation::default_merge(),
+            spawn_animation: animation::default_spawn(),
+            game_over_animation: animation::default_game_over(),
+            animation_speed: animation::default_speed(),
+            tile_radius: display_list::default_tile_radius(),
             width: default_size(),
             height: default_size(),
+            input_queue_size: input::default_queue_size(),
+            snap_animations: false,
+            frame_rate: None,
+            swipe_sensitivity: gesture::default_sensitivity(),
+            scroll_gestures: false,
+            keys: KeyBindings::default(),
+            theme: theme::default_theme(),
+            font: None,
         }
     }
 }
@@ -32,9 +103,33 @@ fn default_size() -> usize {
     4
 }
 
+/// Location of the configuration file.
+pub fn default_path() -> Option<PathBuf> {
+    let mut path = dirs::config_dir()?;
+    path.push("iron");
+    path.push("config.toml");
+    Some(path)
+}
+
+/// Read the configuration file, using the defaults if there is none.
+pub fn load() -> anyhow::Result<Config> {
+    let Some(path) = default_path() else {
+        return Ok(Config::default());
+    };
+
+    let s = match std::fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+    };
+
+    toml::from_str(&s).with_context(|| format!("invalid configuration in {}", path.display()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keys::{Action, Key};
 
     #[test]
     fn parse() {
@@ -43,5 +138,62 @@ mod tests {
         "#;
         let config: Config = toml::from_str(s).unwrap();
         assert_eq!(1500, config.animation_duration_ms);
+        assert_eq!(input::default_queue_size(), config.input_queue_size);
+        assert!(!config.snap_animations);
+    }
+
+    #[test]
+    fn parse_animation() {
+        let s = r#"
+            easing = "cubic"
+
+            [spawn_animation]
+            duration_ms = 200
+            easing = "elastic"
+
+            [game_over_animation]
+            duration_ms = 500
+        "#;
+        let config: Config = toml::from_str(s).unwrap();
+        assert_eq!(Easing::Cubic, config.easing);
+        assert_eq!(200, config.spawn_animation.duration_ms);
+        assert_eq!(Easing::Elastic, config.spawn_animation.easing);
+        assert_eq!(animation::default_merge(), config.merge_animation);
+        // the same default curve as sliding tiles
+        assert_eq!(Config::default().easing, config.game_over_animation.easing);
+    }
+
+    #[test]
+    fn parse_keys() {
+        let s = r#"
+            [keys]
+            quit = "x"
+        "#;
+        let config: Config = toml::from_str(s).unwrap();
+        assert_eq!(Some(Action::Quit), config.keys.action(Key::Character('x')));
+
+        let s = r#"
+            [keys]
+            left = "q"
+        "#;
+        assert!(toml::from_str::<Config>(s).is_err());
+    }
+
+    #[test]
+    fn parse_input() {
+        let s = r#"
+            input_queue_size = 0
+            snap_animations = true
+        "#;
+        let config: Config = toml::from_str(s).unwrap();
+        assert_eq!(0, config.input_queue_size);
+        assert!(config.snap_animations);
+    }
+
+    #[test]
+    fn parse_frame_rate() {
+        let config: Config = toml::from_str("frame_rate = 60").unwrap();
+        assert_eq!(NonZeroU32::new(60), config.frame_rate);
+        assert!(toml::from_str::<Config>("frame_rate = 0").is_err());
     }
 }
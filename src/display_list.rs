@@ -0,0 +1,735 @@
+//! What to draw in a frame, as rects and texts in pixels, independently of
+//! how it is drawn. The positions and colours of tiles, animations, overlays
+//! and widgets are all decided here, so that every backend shows the same
+//! picture.
+
+use crate::animation::Progress;
+use crate::game::{Game, TileId, Value};
+use crate::layout::Layout;
+use crate::text::TextStyle;
+use crate::theme::Theme;
+use crate::ui::{Kind, Status, Widget};
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+
+/// Relative growth of merged tiles at the peak of their pulse.
+const MERGE_PULSE: f32 = 0.15;
+
+/// Opacity of the screen shown over the board.
+const OVERLAY_ALPHA: f32 = 0.75;
+
+pub fn default_tile_radius() -> f32 {
+    0.03
+}
+
+/// How a tile or a text is drawn relative to its rect. The text of a tile
+/// has the same transform, so that it stays on the tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// Scale around the centre of the rect.
+    pub scale: f32,
+    /// Counterclockwise rotation around the centre of the rect, in radians.
+    pub rotation: f32,
+    /// Factor of the alpha of colours.
+    pub opacity: f32,
+    /// Order among the tiles, or the texts, of a layer: greater depths are
+    /// drawn above, and equal depths in the order given.
+    pub depth: f32,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        scale: 1.0,
+        rotation: 0.0,
+        opacity: 1.0,
+        depth: 0.0,
+    };
+
+    /// Where a point of a rect centred on `centre` is drawn.
+    pub fn apply(&self, centre: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = ((p.0 - centre.0) * self.scale, (p.1 - centre.1) * self.scale);
+        (centre.0 + x * cos - y * sin, centre.1 + x * sin + y * cos)
+    }
+
+    /// The point of a rect centred on `centre` that is drawn at `p`, if the
+    /// rect is not scaled down to nothing.
+    pub fn invert(&self, centre: (f32, f32), p: (f32, f32)) -> Option<(f32, f32)> {
+        if self.scale <= 0.0 {
+            return None;
+        }
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (p.0 - centre.0, p.1 - centre.1);
+        Some((
+            centre.0 + (x * cos + y * sin) / self.scale,
+            centre.1 + (y * cos - x * sin) / self.scale,
+        ))
+    }
+
+    /// Bounding box of a rect, given by its left, bottom, right and top
+    /// edges, once drawn transformed around `centre`.
+    pub fn bounds(&self, centre: (f32, f32), [x0, y0, x1, y1]: [f32; 4]) -> [f32; 4] {
+        let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].map(|p| self.apply(centre, p));
+        corners.iter().fold(
+            [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+            |[x0, y0, x1, y1], &(x, y)| [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Left, bottom, right and top edges of a rect.
+pub fn edges(rect: [u32; 4]) -> [f32; 4] {
+    let [x, y, w, h] = rect.map(|v| v as f32);
+    [x, y, x + w, y + h]
+}
+
+/// Centre of a rect, that transforms scale and rotate around.
+pub fn centre(rect: [u32; 4]) -> (f32, f32) {
+    let [x0, y0, x1, y1] = edges(rect);
+    ((x0 + x1) / 2.0, (y0 + y1) / 2.0)
+}
+
+/// Something drawn with a transform.
+pub trait Transformed {
+    fn transform(&self) -> &Transform;
+}
+
+/// Items in the order they are drawn: by increasing depth, and in the given
+/// order for equal depths.
+pub fn by_depth<'a, T: Transformed>(items: impl IntoIterator<Item = &'a T>) -> Vec<&'a T> {
+    let mut items = items.into_iter().collect::<Vec<_>>();
+    items.sort_by(|a, b| a.transform().depth.total_cmp(&b.transform().depth));
+    items
+}
+
+/// A filled rect, with the origin at the bottom left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub colour: [f32; 3],
+    pub alpha: f32,
+    pub rect: [u32; 4],
+    pub transform: Transform,
+}
+
+/// A piece of text to draw in a rect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    pub rect: [u32; 4],
+    pub text: String,
+    pub colour: [f32; 3],
+    pub style: TextStyle,
+    pub transform: Transform,
+}
+
+impl Transformed for Tile {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Transformed for Text {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+/// Tiles and texts drawn together: tiles first, then texts, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layer {
+    /// Whether tiles have rounded corners, with the radius of the display
+    /// list.
+    pub rounded: bool,
+    pub tiles: Vec<Tile>,
+    pub texts: Vec<Text>,
+}
+
+/// Layers of a frame, from bottom to top. A layer is always at the same
+/// index, even if empty.
+pub const CELLS: usize = 0;
+pub const STATIONARY: usize = 1;
+pub const MOVING: usize = 2;
+pub const OVERLAY: usize = 3;
+pub const WIDGETS: usize = 4;
+const LAYERS: usize = 5;
+
+/// Everything drawn in a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayList {
+    pub background: [f32; 3],
+    /// Radius of rounded tiles, relative to their smallest side.
+    pub radius: f32,
+    pub layers: Vec<Layer>,
+}
+
+/// Screen shown over the board, e.g. a menu or a message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overlay {
+    pub title: String,
+    /// Whether the screen holds a menu, in which case the title only takes
+    /// the top of the board.
+    pub menu: bool,
+}
+
+impl Overlay {
+    pub fn message(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            menu: false,
+        }
+    }
+
+    pub fn menu(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            menu: true,
+        }
+    }
+}
+
+/// The state of the app to show in a frame.
+pub struct Frame<'a, Id> {
+    pub layout: &'a Layout,
+    pub theme: &'a Theme,
+    /// Radius of tiles, as in the configuration.
+    pub radius: f32,
+    pub game: &'a Game,
+    /// Board `game` is animating towards, if any.
+    pub target: Option<&'a Game>,
+    pub progress: Progress,
+    pub overlay: Option<&'a Overlay>,
+    /// Widgets, with their status and whether they have focus.
+    pub widgets: Vec<(&'a Widget<Id>, Status, bool)>,
+}
+
+/// Animation state of a single tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TileState {
+    value: Value,
+    /// Position in board coordinates.
+    pos: (f32, f32),
+    transform: Transform,
+    /// Whether the tile is moving, in which case it is drawn above
+    /// stationary tiles.
+    moving: bool,
+}
+
+/// State of every tile, as `game` transitions towards `target`, by id so
+/// that tiles are always drawn in the same order.
+fn track(game: &Game, target: Option<&Game>, progress: Progress) -> BTreeMap<TileId, TileState> {
+    match target {
+        Some(target) if progress.sliding => slide(game, target, progress.slide),
+        Some(target) => settle(target, Some(game), progress),
+        None => settle(game, None, Progress::DONE),
+    }
+}
+
+/// Slide the tiles of `game` towards their position in `target`.
+fn slide(game: &Game, target: &Game, time: f32) -> BTreeMap<TileId, TileState> {
+    let width = game.width();
+    let point = |i: usize| ((i % width) as f32, (i / width) as f32);
+
+    let mut states = BTreeMap::new();
+    for (i, value) in game.tiles.iter().enumerate() {
+        let (Some(value), Some(id)) = (*value, game.id(i)) else {
+            continue;
+        };
+
+        // a tile either slides to a new position, or merges into a child
+        let src = point(i);
+        let dst = target
+            .index_of(id)
+            .or_else(|| {
+                (0..target.tiles.len()).find(|&j| {
+                    target
+                        .id(j)
+                        .and_then(|child| target.parents(child))
+                        .is_some_and(|(a, b)| a == id || b == id)
+                })
+            })
+            .map(point)
+            .unwrap_or(src);
+
+        states.insert(
+            id,
+            TileState {
+                value,
+                pos: (
+                    src.0 + (dst.0 - src.0) * time,
+                    src.1 + (dst.1 - src.1) * time,
+                ),
+                transform: Transform::IDENTITY,
+                moving: src != dst,
+            },
+        );
+    }
+    states
+}
+
+/// Place the tiles of `game` in their final position. Tiles that were merged
+/// or added since `previous` pulse or pop in.
+fn settle(game: &Game, previous: Option<&Game>, progress: Progress) -> BTreeMap<TileId, TileState> {
+    let width = game.width();
+    let mut states = BTreeMap::new();
+    for (i, value) in game.tiles.iter().enumerate() {
+        let (Some(value), Some(id)) = (*value, game.id(i)) else {
+            continue;
+        };
+
+        let transform = match previous {
+            // merged tiles pulse over their neighbours
+            Some(previous) if previous.index_of(id).is_none() && game.parents(id).is_some() => {
+                Transform {
+                    scale: 1.0 + MERGE_PULSE * (progress.merge * PI).sin(),
+                    depth: 1.0,
+                    ..Transform::IDENTITY
+                }
+            }
+            Some(previous) if previous.index_of(id).is_none() => Transform {
+                scale: progress.spawn.max(0.0),
+                ..Transform::IDENTITY
+            },
+            _ => Transform::IDENTITY,
+        };
+
+        states.insert(
+            id,
+            TileState {
+                value,
+                pos: ((i % width) as f32, (i / width) as f32),
+                transform,
+                moving: false,
+            },
+        );
+    }
+    states
+}
+
+/// Compute what to draw for a frame.
+pub fn build<Id>(frame: &Frame<Id>) -> DisplayList {
+    let (layout, theme) = (frame.layout, frame.theme);
+    let mut layers = vec![Layer::default(); LAYERS];
+
+    // empty cells
+    layers[CELLS].tiles = frame
+        .game
+        .all_tiles()
+        .map(|(pos, _)| Tile {
+            colour: theme.empty.0,
+            alpha: 1.0,
+            rect: layout.rect(pos),
+            transform: Transform::IDENTITY,
+        })
+        .collect();
+
+    // stationary tiles first, so that moving ones slide over them
+    for state in track(frame.game, frame.target, frame.progress).values() {
+        let layer = &mut layers[if state.moving { MOVING } else { STATIONARY }];
+        let rect = layout.rect_at(state.pos);
+        layer.tiles.push(Tile {
+            colour: theme.tiles.colour(state.value),
+            alpha: 1.0,
+            rect,
+            transform: state.transform,
+        });
+        layer.texts.push(Text {
+            rect,
+            text: format!("{}", 1_u64 << state.value),
+            colour: theme.text.colour(state.value),
+            style: TextStyle::TILE,
+            transform: state.transform,
+        });
+    }
+
+    if let Some(overlay) = frame.overlay {
+        let alpha = if frame.target.unwrap_or(frame.game).is_over() {
+            frame.progress.game_over.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        layers[OVERLAY] = overlay_layer(layout, theme, overlay, alpha);
+    }
+
+    layers[WIDGETS] = widget_layer(layout, theme, &frame.widgets);
+
+    for (i, layer) in layers.iter_mut().enumerate() {
+        layer.rounded = i != OVERLAY;
+    }
+    DisplayList {
+        background: theme.background.0,
+        radius: frame.radius,
+        layers,
+    }
+}
+
+/// A screen over the board, with a title, fading in with `alpha`.
+fn overlay_layer(layout: &Layout, theme: &Theme, overlay: &Overlay, alpha: f32) -> Layer {
+    let board = [
+        layout.origin.0,
+        layout.origin.1,
+        layout.size.0,
+        layout.size.1,
+    ];
+    let mut layer = Layer {
+        tiles: vec![Tile {
+            colour: theme.overlay.0,
+            alpha: alpha * OVERLAY_ALPHA,
+            rect: board,
+            transform: Transform::IDENTITY,
+        }],
+        ..Layer::default()
+    };
+    if alpha > 0.0 {
+        layer.texts.push(Text {
+            rect: if overlay.menu {
+                layout.menu_title()
+            } else {
+                board
+            },
+            text: overlay.title.clone(),
+            colour: theme.overlay_text.0,
+            style: TextStyle::OVERLAY,
+            transform: Transform::IDENTITY,
+        });
+    }
+    layer
+}
+
+/// Widgets, with the colours of increasingly large tiles as they are hovered
+/// and pressed.
+fn widget_layer<Id>(
+    layout: &Layout,
+    theme: &Theme,
+    widgets: &[(&Widget<Id>, Status, bool)],
+) -> Layer {
+    let gap = layout.gap.max(2);
+    let tile = |colour: [f32; 3], rect: [u32; 4]| Tile {
+        colour,
+        alpha: 1.0,
+        rect,
+        transform: Transform::IDENTITY,
+    };
+    let text = |rect, text: &str, colour, style| Text {
+        rect,
+        text: text.to_string(),
+        colour,
+        style,
+        transform: Transform::IDENTITY,
+    };
+
+    let mut tiles = Vec::new();
+    let mut texts = Vec::new();
+    for &(widget, status, focused) in widgets {
+        let value: Value = match status {
+            Status::Normal => 1,
+            Status::Hover => 2,
+            Status::Pressed => 3,
+        };
+        let (bg, fg) = (theme.tiles.colour(value), theme.text.colour(value));
+        let [x, y, w, h] = widget.rect;
+        let control = widget.control();
+        let [cx, cy, cw, ch] = control;
+        let label = [x, y, w - cw, h];
+
+        if focused {
+            let [x, y, w, h] = [
+                x.saturating_sub(gap / 2),
+                y.saturating_sub(gap / 2),
+                w + gap,
+                h + gap,
+            ];
+            tiles.push(tile(theme.overlay_text.0, [x, y, w, h]));
+        }
+
+        match &widget.kind {
+            Kind::Label => texts.push(text(
+                widget.rect,
+                &widget.text,
+                theme.overlay_text.0,
+                TextStyle::ITEM,
+            )),
+            Kind::Value(v) => {
+                let colour = theme.overlay_text.0;
+                let caption = h * 2 / 5;
+                tiles.push(tile(theme.overlay.0, widget.rect));
+                texts.push(text(
+                    [x, y + h - caption, w, caption],
+                    &widget.text,
+                    colour,
+                    TextStyle::LABEL,
+                ));
+                texts.push(text([x, y, w, h - caption], v, colour, TextStyle::VALUE));
+            }
+            Kind::Button => {
+                tiles.push(tile(bg, widget.rect));
+                texts.push(text(widget.rect, &widget.text, fg, TextStyle::ITEM));
+            }
+            Kind::Toggle(on) => {
+                tiles.push(tile(bg, widget.rect));
+                texts.push(text(label, &widget.text, fg, TextStyle::FIELD));
+                let state = if *on { "On" } else { "Off" };
+                texts.push(text(control, state, fg, TextStyle::FIELD_VALUE));
+            }
+            Kind::Slider { index, count } => {
+                tiles.push(tile(bg, widget.rect));
+                texts.push(text(label, &widget.text, fg, TextStyle::FIELD));
+
+                let pad = ch / 5;
+                let knob = ch - 2 * pad;
+                let track = cw.saturating_sub(knob + 2 * pad);
+                let t = *index as f32 / count.saturating_sub(1).max(1) as f32;
+                tiles.push(tile(
+                    theme.empty.0,
+                    [
+                        cx + pad,
+                        cy + ch * 2 / 5,
+                        cw.saturating_sub(2 * pad),
+                        ch / 5,
+                    ],
+                ));
+                tiles.push(tile(
+                    theme.tiles.colour(value + 2),
+                    [cx + pad + (t * track as f32) as u32, cy + pad, knob, knob],
+                ));
+            }
+            Kind::List { items, selected } => {
+                tiles.push(tile(bg, widget.rect));
+                texts.push(text(label, &widget.text, fg, TextStyle::FIELD));
+
+                let n = items.len().max(1) as u32;
+                for (i, item) in items.iter().enumerate() {
+                    let rect = [cx + i as u32 * cw / n, cy, cw / n, ch];
+                    let colour = if i == *selected {
+                        let inner = [
+                            rect[0] + gap / 2,
+                            cy + gap / 2,
+                            rect[2].saturating_sub(gap),
+                            ch.saturating_sub(gap),
+                        ];
+                        tiles.push(tile(theme.tiles.colour(value + 2), inner));
+                        theme.text.colour(value + 2)
+                    } else {
+                        fg
+                    };
+                    texts.push(text(rect, item, colour, TextStyle::ITEM));
+                }
+            }
+        }
+    }
+
+    Layer {
+        rounded: true,
+        tiles,
+        texts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Timeline;
+    use crate::config::Config;
+    use crate::game::Direction;
+    use crate::state::State;
+
+    fn frame<'a>(layout: &'a Layout, theme: &'a Theme, game: &'a Game) -> Frame<'a, ()> {
+        Frame {
+            layout,
+            theme,
+            radius: 0.1,
+            game,
+            target: None,
+            progress: Progress::DONE,
+            overlay: None,
+            widgets: Vec::new(),
+        }
+    }
+
+    fn game() -> Game {
+        let mut game = Game::new(4, 4);
+        game.tiles[0] = Some(1);
+        game.tiles[2] = Some(1);
+        game.tiles[4] = Some(3);
+        game.assign_ids();
+        game
+    }
+
+    #[test]
+    fn at_rest() {
+        let layout = Layout::compute(400, 300, 4, 4);
+        let theme = Theme::builtin("default").unwrap();
+        let game = game();
+        let list = build(&frame(&layout, &theme, &game));
+
+        assert_eq!(16, list.layers[CELLS].tiles.len());
+        let stationary = &list.layers[STATIONARY];
+        let texts = stationary.texts.iter().map(|t| t.text.as_str());
+        assert_eq!(vec!["2", "2", "8"], texts.collect::<Vec<_>>());
+        assert_eq!(layout.rect((0, 1)), stationary.tiles[2].rect);
+        assert_eq!(theme.tiles.colour(3), stationary.tiles[2].colour);
+        assert!(list.layers[MOVING].tiles.is_empty());
+        assert!(list.layers[OVERLAY].tiles.is_empty());
+    }
+
+    #[test]
+    fn sliding() {
+        let layout = Layout::compute(400, 300, 4, 4);
+        let theme = Theme::builtin("default").unwrap();
+        let game = game();
+        let mut target = game.clone();
+        target.step(Direction::W);
+        let timeline = Timeline::from_config(&Config::default());
+
+        let mut frame = frame(&layout, &theme, &game);
+        frame.target = Some(&target);
+        frame.progress = timeline.progress(timeline.slide.duration / 2);
+        let list = build(&frame);
+
+        // the tile at (2, 0) slides onto the one at (0, 0)
+        let moving = &list.layers[MOVING].tiles;
+        assert_eq!(1, moving.len());
+        let start = layout.rect((2, 0));
+        let end = layout.rect((0, 0));
+        assert!(end[0] < moving[0].rect[0] && moving[0].rect[0] < start[0]);
+        assert_eq!(2, list.layers[STATIONARY].tiles.len());
+
+        // once done, the merged tile replaces both
+        frame.progress = Progress::DONE;
+        let list = build(&frame);
+        let texts = list.layers[STATIONARY]
+            .texts
+            .iter()
+            .map(|t| t.text.as_str());
+        assert_eq!(vec!["8", "4"], texts.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn merge_and_spawn_transforms() {
+        let layout = Layout::compute(400, 300, 4, 4);
+        let theme = Theme::builtin("default").unwrap();
+        let game = game();
+        let mut target = game.clone();
+        target.step(Direction::W);
+        target.add_tile(15, 1);
+
+        let mut frame = frame(&layout, &theme, &game);
+        frame.target = Some(&target);
+        frame.progress = Progress {
+            sliding: false,
+            merge: 0.5,
+            spawn: 0.5,
+            ..Progress::DONE
+        };
+        let list = build(&frame);
+        let layer = &list.layers[STATIONARY];
+        let tile = |text: &str| {
+            let i = layer.texts.iter().position(|t| t.text == text).unwrap();
+            (&layer.tiles[i], &layer.texts[i])
+        };
+
+        // the merged tile pulses above the others, with its text
+        let (merged, text) = tile("4");
+        assert_eq!(layout.rect((0, 0)), merged.rect);
+        assert_eq!(1.0 + MERGE_PULSE, merged.transform.scale);
+        assert_eq!(merged.transform, text.transform);
+        assert_eq!(merged, *by_depth(&layer.tiles).last().unwrap());
+
+        // the new tile pops in, at its full rect
+        let (spawned, _) = tile("2");
+        assert_eq!(layout.rect((3, 3)), spawned.rect);
+        assert_eq!(0.5, spawned.transform.scale);
+        assert_eq!(0.0, spawned.transform.depth);
+        assert_eq!(Transform::IDENTITY, tile("8").0.transform);
+    }
+
+    #[test]
+    fn transforms() {
+        let transform = Transform {
+            scale: 2.0,
+            rotation: PI / 2.0,
+            ..Transform::IDENTITY
+        };
+        let (x, y) = transform.apply((1.0, 1.0), (2.0, 1.0));
+        assert!((x - 1.0).abs() < 1e-6 && (y - 3.0).abs() < 1e-6);
+        let (x, y) = transform.invert((1.0, 1.0), (x, y)).unwrap();
+        assert!((x - 2.0).abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+
+        let [x0, y0, x1, y1] = transform.bounds((1.0, 1.0), [0.0, 0.0, 2.0, 2.0]);
+        assert!((x0 + 1.0).abs() < 1e-6 && (x1 - 3.0).abs() < 1e-6);
+        assert!((y0 + 1.0).abs() < 1e-6 && (y1 - 3.0).abs() < 1e-6);
+        let hidden = Transform {
+            scale: 0.0,
+            ..Transform::IDENTITY
+        };
+        assert_eq!(None, hidden.invert((0.0, 0.0), (1.0, 1.0)));
+    }
+
+    #[test]
+    fn tiny_viewports() {
+        let theme = Theme::builtin("default").unwrap();
+        let game = game();
+        let hud = crate::hud::Hud {
+            score: 0,
+            best: 0,
+            moves: 0,
+            time: std::time::Duration::ZERO,
+            mode: crate::hud::mode(4, 4),
+        };
+        let settings = crate::settings::Settings::from_config(&Config::default());
+        let stats = crate::stats::Stats::default();
+        let states = [
+            State::Menu,
+            State::Playing,
+            State::Paused,
+            State::GameOver,
+            State::Settings { paused: false },
+            State::Stats { paused: false },
+        ];
+        // e.g. a minimised window
+        for (w, h) in [(0, 0), (1, 1), (2, 1), (1, 2)] {
+            let layout = Layout::compute(w, h, 4, 4);
+            for state in states {
+                let widgets = state.widgets(&layout, &hud, &settings, &stats);
+                let overlay = state.title().map(Overlay::menu);
+                build(&Frame {
+                    layout: &layout,
+                    theme: &theme,
+                    radius: 0.1,
+                    game: &game,
+                    target: None,
+                    progress: Progress::DONE,
+                    overlay: overlay.as_ref(),
+                    widgets: widgets.iter().map(|w| (w, Status::Normal, true)).collect(),
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn overlay_fades_in_on_game_over() {
+        let layout = Layout::compute(400, 300, 2, 2);
+        let theme = Theme::builtin("default").unwrap();
+        let mut game = Game::new(2, 2);
+        game.tiles = vec![Some(1), Some(2), Some(3), Some(4)];
+        game.assign_ids();
+        let overlay = Overlay::message("Game over");
+
+        let mut frame = frame(&layout, &theme, &game);
+        frame.overlay = Some(&overlay);
+        frame.progress.game_over = 0.0;
+        let layer = &build(&frame).layers[OVERLAY];
+        assert_eq!(0.0, layer.tiles[0].alpha);
+        assert!(layer.texts.is_empty());
+        assert!(!layer.rounded);
+
+        frame.progress = Progress::DONE;
+        let layer = &build(&frame).layers[OVERLAY];
+        assert_eq!(OVERLAY_ALPHA, layer.tiles[0].alpha);
+        assert_eq!("Game over", layer.texts[0].text);
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::{anyhow, Context};
+use fontdb::{Database, Family, Query, Weight};
+use rusttype::Font;
+use std::path::Path;
+
+/// Font bundled in the binary, used when no other font is configured or
+/// available.
+const FALLBACK: &[u8] = include_bytes!("../fonts/DejaVuSansMono-Bold.ttf");
+
+pub fn fallback() -> Font<'static> {
+    Font::try_from_bytes(FALLBACK).expect("invalid bundled font")
+}
+
+/// Load a font given either the path of a font file or a family name, which
+/// is looked up among the fonts installed on the system.
+pub fn load(name: &str) -> anyhow::Result<Font<'static>> {
+    let path = Path::new(name);
+    let (data, index) = if path.extension().is_some() || path.components().count() > 1 {
+        let data = std::fs::read(path)
+            .with_context(|| format!("could not read font {}", path.display()))?;
+        (data, 0)
+    } else {
+        find_system_font(name)?
+    };
+    Font::try_from_vec_and_index(data, index).ok_or_else(|| anyhow!("invalid font `{}`", name))
+}
+
+/// Find a font by family name, preferring a bold face.
+fn find_system_font(family: &str) -> anyhow::Result<(Vec<u8>, u32)> {
+    let mut db = Database::new();
+    db.load_system_fonts();
+
+    let query = Query {
+        families: &[Family::Name(family)],
+        weight: Weight::BOLD,
+        ..Query::default()
+    };
+    let id = db
+        .query(&query)
+        .ok_or_else(|| anyhow!("font family `{}` not found", family))?;
+    db.with_face_data(id, |data, index| (data.to_vec(), index))
+        .ok_or_else(|| anyhow!("could not load font family `{}`", family))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_has_ascii() {
+        let font = fallback();
+        for c in 0x20..0x7f_u8 {
+            assert_ne!(0, font.glyph(c as char).id().0, "{}", c as char);
+        }
+    }
+
+    #[test]
+    fn missing() {
+        let err = load("/no/such/font.ttf").unwrap_err().to_string();
+        assert!(err.contains("could not read font"), "{}", err);
+
+        let err = load("No Such Font Family").unwrap_err().to_string();
+        assert!(err.contains("not found"), "{}", err);
+    }
+
+    #[test]
+    fn invalid() {
+        let path = std::env::temp_dir().join("iron-invalid-font.ttf");
+        std::fs::write(&path, b"not a font").unwrap();
+        assert!(load(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,16 +1,39 @@
 use rand::Rng;
+use std::collections::HashMap;
 
 pub type Value = u8;
 
+/// Persistent identity of a tile, preserved when the tile slides.
+pub type TileId = u32;
+
 /// State of the game.
-#[allow(dead_code)]
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct Game {
     width: usize,
     height: usize,
     pub tiles: Vec<Option<Value>>,
+    /// Identity of the tile at each position.
+    ids: Vec<Option<TileId>>,
+    /// Position of each identity in `ids`.
+    positions: HashMap<TileId, usize>,
+    next_id: TileId,
+    /// Parents of the tiles created by merging in the last step.
+    parents: HashMap<TileId, (TileId, TileId)>,
+    /// Sum of the numbers on all tiles created by merging.
+    pub score: u64,
+    /// Number of steps that changed the board.
+    pub moves: u32,
 }
 
+/// Two games are equal when their boards hold the same values, regardless of
+/// tile identities and scores.
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.tiles == other.tiles
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Direction {
     E,
     N,
@@ -35,10 +58,18 @@ impl Game {
     pub fn new(width: usize, height: usize) -> Self {
         let mut tiles = Vec::new();
         tiles.resize(width * height, None);
+        let mut ids = Vec::new();
+        ids.resize(width * height, None);
         Game {
             width,
             height,
             tiles,
+            ids,
+            positions: HashMap::new(),
+            next_id: 0,
+            parents: HashMap::new(),
+            score: 0,
+            moves: 0,
         }
     }
 
@@ -60,9 +91,74 @@ impl Game {
         (index, value)
     }
 
-    pub fn add_random_tile(&mut self) {
+    /// Add a tile on a random empty cell, and return its index and value.
+    pub fn add_random_tile(&mut self) -> (usize, Value) {
         let (index, value) = self.random_tile();
+        self.add_tile(index, value);
+        (index, value)
+    }
+
+    /// Add a new tile on an empty cell.
+    pub fn add_tile(&mut self, index: usize, value: Value) {
         self.tiles[index] = Some(value);
+        let id = self.fresh_id();
+        self.set_id(index, Some(id));
+    }
+
+    /// Set the identity at a position, keeping `positions` in sync.
+    fn set_id(&mut self, index: usize, id: Option<TileId>) {
+        if let Some(old) = self.ids[index] {
+            if self.positions.get(&old) == Some(&index) {
+                self.positions.remove(&old);
+            }
+        }
+        self.ids[index] = id;
+        if let Some(id) = id {
+            self.positions.insert(id, index);
+        }
+    }
+
+    fn take_id(&mut self, index: usize) -> Option<TileId> {
+        let id = self.ids[index];
+        self.set_id(index, None);
+        id
+    }
+
+    fn fresh_id(&mut self) -> TileId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Give an identity to every tile that does not have one yet, e.g. tiles
+    /// placed directly in `tiles`.
+    pub fn assign_ids(&mut self) {
+        for i in 0..self.tiles.len() {
+            if self.tiles[i].is_none() {
+                self.set_id(i, None);
+            } else if self.ids[i].is_none() {
+                let id = self.fresh_id();
+                self.set_id(i, Some(id));
+            }
+        }
+    }
+
+    /// Identity of the tile at the given index.
+    pub fn id(&self, index: usize) -> Option<TileId> {
+        self.tiles[index].and(self.ids[index])
+    }
+
+    /// Index of the tile with the given identity, if it is on the board.
+    pub fn index_of(&self, id: TileId) -> Option<usize> {
+        // tiles may have been removed from `tiles` since
+        let &i = self.positions.get(&id)?;
+        (self.id(i) == Some(id)).then_some(i)
+    }
+
+    /// Identities of the two tiles that merged into the given one during the
+    /// last step.
+    pub fn parents(&self, id: TileId) -> Option<(TileId, TileId)> {
+        self.parents.get(&id).copied()
     }
 
     pub fn all_tiles<'a>(
@@ -87,22 +183,41 @@ impl Game {
                 return false;
             }
         }
-        return true;
+        true
+    }
+
+    /// Suggest a move, greedily maximising the number of empty cells after it.
+    pub fn hint(&self) -> Option<Direction> {
+        let mut best = None;
+        for dir in [Direction::W, Direction::S, Direction::E, Direction::N] {
+            let mut game = self.clone();
+            if game.step(dir).is_empty() {
+                continue;
+            }
+            let empty = game.tiles.iter().filter(|t| t.is_none()).count();
+            if best.is_none_or(|(_, e)| empty > e) {
+                best = Some((dir, empty));
+            }
+        }
+        best.map(|(dir, _)| dir)
     }
 
     pub fn step(&mut self, dir: Direction) -> Vec<Move> {
         let mut moves = Vec::new();
+        self.assign_ids();
+        self.parents.clear();
 
         let (width, height) = match dir {
             Direction::S | Direction::N => (self.width(), self.height()),
             Direction::E | Direction::W => (self.height(), self.width()),
         };
 
+        let stride = self.width;
         let get: Box<dyn Fn(usize, usize) -> usize> = match dir {
-            Direction::E => Box::new(|x, y| height - y - 1 + x * self.width),
-            Direction::N => Box::new(|x, y| x + (height - y - 1) * self.width),
-            Direction::W => Box::new(|x, y| y + x * self.width),
-            Direction::S => Box::new(|x, y| x + y * self.width),
+            Direction::E => Box::new(|x, y| height - y - 1 + x * stride),
+            Direction::N => Box::new(|x, y| x + (height - y - 1) * stride),
+            Direction::W => Box::new(|x, y| y + x * stride),
+            Direction::S => Box::new(|x, y| x + y * stride),
         };
 
         for x in 0..width {
@@ -119,11 +234,20 @@ impl Game {
                         None => {
                             self.tiles[i1] = None;
                             self.tiles[i0] = Some(v);
+                            let id = self.take_id(i1);
+                            self.set_id(i0, id);
                             moves.push(Move::new(i1, i0, false));
                         }
                         Some(w) if w == v => {
+                            let id = self.next_id;
+                            self.next_id += 1;
+                            let parents = (self.ids[i0].unwrap(), self.take_id(i1).unwrap());
+                            self.parents.insert(id, parents);
+
                             self.tiles[i1] = None;
                             self.tiles[i0] = Some(v + 1);
+                            self.set_id(i0, Some(id));
+                            self.score += 1 << (v + 1);
                             moves.push(Move::new(i1, i0, true));
                             y0 += 1;
                         }
@@ -133,6 +257,8 @@ impl Game {
                             if i0 != i1 {
                                 self.tiles[i1] = None;
                                 self.tiles[i0] = Some(v);
+                                let id = self.take_id(i1);
+                                self.set_id(i0, id);
                                 moves.push(Move::new(i1, i0, false));
                             }
                         }
@@ -141,6 +267,9 @@ impl Game {
             }
         }
 
+        if !moves.is_empty() {
+            self.moves += 1;
+        }
         moves
     }
 }
@@ -351,6 +480,99 @@ mod tests {
         assert_eq!(game, game2);
     }
 
+    #[test]
+    fn test_ids_survive_slides() {
+        let mut game = Game::new(4, 4);
+        game.tiles[4] = Some(0);
+        game.tiles[9] = Some(1);
+        game.step(Direction::S);
+        let a = game.id(0).unwrap();
+        let b = game.id(1).unwrap();
+        assert_ne!(a, b);
+
+        game.step(Direction::E);
+        assert_eq!(Some(2), game.index_of(a));
+        assert_eq!(Some(3), game.index_of(b));
+        assert_eq!(None, game.parents(a));
+    }
+
+    #[test]
+    fn test_index_of_follows_tiles() {
+        let mut game = Game::new(4, 4);
+        game.tiles[5] = Some(1);
+        game.tiles[6] = Some(2);
+        game.assign_ids();
+        let (a, b) = (game.id(5).unwrap(), game.id(6).unwrap());
+        game.step(Direction::W);
+        assert_eq!((Some(4), Some(5)), (game.index_of(a), game.index_of(b)));
+
+        // removed directly from the board
+        game.tiles[4] = None;
+        assert_eq!(None, game.index_of(a));
+        game.assign_ids();
+        assert_eq!(None, game.index_of(a));
+        assert_eq!(Some(5), game.index_of(b));
+    }
+
+    #[test]
+    fn test_merge_parents() {
+        let mut game = Game::new(4, 4);
+        game.tiles[4] = Some(1);
+        game.tiles[5] = Some(1);
+        game.step(Direction::S);
+        let a = game.id(0).unwrap();
+        let b = game.id(1).unwrap();
+
+        game.step(Direction::E);
+        let id = game.id(3).unwrap();
+        assert_ne!(id, a);
+        assert_ne!(id, b);
+        assert_eq!(Some((b, a)), game.parents(id));
+        assert_eq!(None, game.index_of(a));
+        assert_eq!(None, game.index_of(b));
+
+        // parents are only recorded for the last step
+        game.step(Direction::W);
+        assert_eq!(None, game.parents(id));
+    }
+
+    #[test]
+    fn test_score() {
+        let mut game = Game::new(4, 4);
+        game.tiles[0] = Some(1);
+        game.tiles[4] = Some(1);
+        game.tiles[1] = Some(2);
+        game.tiles[5] = Some(2);
+        game.step(Direction::S);
+        assert_eq!(4 + 8, game.score);
+        assert_eq!(1, game.moves);
+
+        // no change, no move
+        game.step(Direction::W);
+        assert_eq!(1, game.moves);
+
+        game.step(Direction::E);
+        assert_eq!(4 + 8, game.score);
+        assert_eq!(2, game.moves);
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut game = Game::new(4, 4);
+        game.tiles[0] = Some(2);
+        game.tiles[1] = Some(1);
+        game.tiles[4] = Some(2);
+        // only merging the two 4s frees a cell
+        assert_eq!(Some(Direction::S), game.hint());
+
+        let mut game = Game::new(2, 2);
+        game.tiles[0] = Some(1);
+        game.tiles[1] = Some(2);
+        game.tiles[2] = Some(3);
+        game.tiles[3] = Some(4);
+        assert_eq!(None, game.hint());
+    }
+
     #[test]
     fn test_game_not_over() {
         let mut game = Game::new(4, 4);
@@ -0,0 +1,193 @@
+use crate::game::Direction;
+use std::collections::HashMap;
+use std::time::Instant;
+
+pub fn default_sensitivity() -> f32 {
+    1.0
+}
+
+/// Minimum length of a swipe at unit sensitivity, in pixels.
+const MIN_DISTANCE: f64 = 40.0;
+/// Minimum average speed of a swipe at unit sensitivity, in pixels per second.
+const MIN_VELOCITY: f64 = 150.0;
+/// Distance a scroll gesture needs to cover at unit sensitivity, in pixels.
+const SCROLL_DISTANCE: f64 = 60.0;
+
+/// Pointer identifier used for mouse drags, distinct from any touch id.
+pub const MOUSE: u64 = u64::MAX;
+
+/// Direction of the dominant axis of a displacement in window coordinates,
+/// where y grows downwards.
+pub fn direction(dx: f64, dy: f64) -> Direction {
+    if dx.abs() > dy.abs() {
+        if dx > 0.0 {
+            Direction::E
+        } else {
+            Direction::W
+        }
+    } else if dy > 0.0 {
+        Direction::S
+    } else {
+        Direction::N
+    }
+}
+
+struct Stroke {
+    start: (f64, f64),
+    time: Instant,
+    last: (f64, f64),
+}
+
+/// Detects swipes from mouse drags and touches.
+pub struct SwipeDetector {
+    min_distance: f64,
+    min_velocity: f64,
+    strokes: HashMap<u64, Stroke>,
+}
+
+impl SwipeDetector {
+    pub fn new(sensitivity: f32) -> Self {
+        let sensitivity = sensitivity.max(f32::EPSILON) as f64;
+        Self {
+            min_distance: MIN_DISTANCE / sensitivity,
+            min_velocity: MIN_VELOCITY / sensitivity,
+            strokes: HashMap::new(),
+        }
+    }
+
+    pub fn begin(&mut self, id: u64, pos: (f64, f64), now: Instant) {
+        self.strokes.insert(
+            id,
+            Stroke {
+                start: pos,
+                time: now,
+                last: pos,
+            },
+        );
+    }
+
+    pub fn moved(&mut self, id: u64, pos: (f64, f64)) {
+        if let Some(stroke) = self.strokes.get_mut(&id) {
+            stroke.last = pos;
+        }
+    }
+
+    pub fn cancel(&mut self, id: u64) {
+        self.strokes.remove(&id);
+    }
+
+    /// Finish a stroke, returning the direction of the swipe if it was long
+    /// and fast enough.
+    pub fn end(&mut self, id: u64, now: Instant) -> Option<Direction> {
+        let stroke = self.strokes.remove(&id)?;
+        let dx = stroke.last.0 - stroke.start.0;
+        let dy = stroke.last.1 - stroke.start.1;
+        let distance = dx.abs().max(dy.abs());
+        let elapsed = (now - stroke.time).as_secs_f64().max(f64::EPSILON);
+
+        if distance < self.min_distance || distance / elapsed < self.min_velocity {
+            return None;
+        }
+        Some(direction(dx, dy))
+    }
+}
+
+/// Turns trackpad scroll gestures into moves, at most one per gesture.
+pub struct ScrollDetector {
+    distance: f64,
+    delta: (f64, f64),
+    fired: bool,
+}
+
+impl ScrollDetector {
+    pub fn new(sensitivity: f32) -> Self {
+        Self {
+            distance: SCROLL_DISTANCE / sensitivity.max(f32::EPSILON) as f64,
+            delta: (0.0, 0.0),
+            fired: false,
+        }
+    }
+
+    /// Start a new gesture.
+    pub fn reset(&mut self) {
+        self.delta = (0.0, 0.0);
+        self.fired = false;
+    }
+
+    /// Accumulate a scroll delta, where positive values move the content
+    /// right and down.
+    pub fn scroll(&mut self, dx: f64, dy: f64) -> Option<Direction> {
+        if self.fired {
+            return None;
+        }
+        self.delta.0 += dx;
+        self.delta.1 += dy;
+        if self.delta.0.abs().max(self.delta.1.abs()) < self.distance {
+            return None;
+        }
+        self.fired = true;
+        Some(direction(self.delta.0, self.delta.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn swipe() {
+        let t0 = Instant::now();
+        let mut swipes = SwipeDetector::new(1.0);
+        swipes.begin(0, (100.0, 100.0), t0);
+        swipes.moved(0, (110.0, 40.0));
+        assert_eq!(
+            Some(Direction::N),
+            swipes.end(0, t0 + Duration::from_millis(100))
+        );
+
+        swipes.begin(MOUSE, (100.0, 100.0), t0);
+        swipes.moved(MOUSE, (200.0, 90.0));
+        assert_eq!(
+            Some(Direction::E),
+            swipes.end(MOUSE, t0 + Duration::from_millis(100))
+        );
+        assert_eq!(None, swipes.end(MOUSE, t0));
+    }
+
+    #[test]
+    fn swipe_thresholds() {
+        let t0 = Instant::now();
+        let mut swipes = SwipeDetector::new(1.0);
+
+        // too short
+        swipes.begin(0, (100.0, 100.0), t0);
+        swipes.moved(0, (100.0, 120.0));
+        assert_eq!(None, swipes.end(0, t0 + Duration::from_millis(50)));
+
+        // too slow
+        swipes.begin(0, (100.0, 100.0), t0);
+        swipes.moved(0, (100.0, 200.0));
+        assert_eq!(None, swipes.end(0, t0 + Duration::from_secs(2)));
+
+        // short, but sensitive enough
+        let mut swipes = SwipeDetector::new(4.0);
+        swipes.begin(0, (100.0, 100.0), t0);
+        swipes.moved(0, (100.0, 120.0));
+        assert_eq!(
+            Some(Direction::S),
+            swipes.end(0, t0 + Duration::from_millis(50))
+        );
+    }
+
+    #[test]
+    fn scroll() {
+        let mut scroll = ScrollDetector::new(1.0);
+        assert_eq!(None, scroll.scroll(-30.0, 5.0));
+        assert_eq!(Some(Direction::W), scroll.scroll(-40.0, 0.0));
+        assert_eq!(None, scroll.scroll(-100.0, 0.0));
+
+        scroll.reset();
+        assert_eq!(Some(Direction::S), scroll.scroll(0.0, 100.0));
+    }
+}
@@ -1,113 +1,139 @@
+use crate::display_list::{self, by_depth, Text};
 use crate::graphics::util::rect;
-use crate::graphics::{GlyphCache, GlyphInfo, Object, Program, Quad, VertexArray, VertexBuffer};
-use std::cell::RefCell;
+use crate::graphics::{
+    GlyphCache, Object, Program, Quad, VertexArray, VertexBuffer, VertexBufferRef,
+};
+use crate::text::layout;
+use anyhow::Context;
+use rusttype::Font;
 use std::rc::Rc;
 
-pub struct Glyphs {
-    obj: Object,
-    quad: Rc<RefCell<Quad>>,
-
+/// Instance data of one draw call, kept across frames so that text that
+/// did not change is not uploaded again.
+struct Batch {
+    vao: VertexArray,
     cell_rects: VertexBuffer<u32>,
     glyph_indices: VertexBuffer<u32>,
-
-    cache: GlyphCache,
-    infos: Vec<GlyphInfo<'static>>,
+    colours: VertexBuffer<f32>,
+    /// Centres of the rects of texts, that glyphs are transformed around.
+    centres: VertexBuffer<f32>,
+    /// Scale, rotation and opacity of texts.
+    transforms: VertexBuffer<f32>,
 }
 
-impl Glyphs {
-    pub fn new(gl: Rc<glow::Context>, quad: Rc<RefCell<Quad>>) -> Self {
+impl Batch {
+    fn new(gl: Rc<glow::Context>, quad: Rc<VertexBufferRef>) -> Self {
         let mut vao = VertexArray::new(gl.clone());
-
-        let program = Program::new(
-            gl.clone(),
-            include_bytes!("../shaders/glyph.v.glsl"),
-            include_bytes!("../shaders/glyph.f.glsl"),
-        );
-
-        vao.add_buffer(quad.borrow().vbo());
+        vao.add_buffer(quad);
 
         // cell rects
-        let cell_rects = VertexBuffer::new(gl.clone(), 4);
+        let cell_rects = VertexBuffer::per_instance(gl.clone(), 4);
 
         // glyph indices
-        let glyph_indices = VertexBuffer::new(gl.clone(), 1);
+        let glyph_indices = VertexBuffer::per_instance(gl.clone(), 1);
 
-        vao.add_buffer(cell_rects.to_ref());
-        vao.add_buffer(glyph_indices.to_ref());
+        // text colours
+        let colours = VertexBuffer::per_instance(gl.clone(), 3);
 
-        let mut cache = GlyphCache::new(gl.clone(), 0);
-        let (infos, texture) = cache.make_atlas();
-        cache.upload_atlas(&texture.bind());
+        // text transforms
+        let centres = VertexBuffer::per_instance(gl.clone(), 2);
+        let transforms = VertexBuffer::per_instance(gl, 3);
 
-        let obj = Object::new(gl, vao, quad.borrow().ebo(), Some(texture), program);
+        vao.add_buffer(cell_rects.to_ref());
+        vao.add_buffer(glyph_indices.to_ref());
+        vao.add_buffer(colours.to_ref());
+        vao.add_buffer(centres.to_ref());
+        vao.add_buffer(transforms.to_ref());
 
         Self {
-            obj,
-            quad,
+            vao,
             cell_rects,
             glyph_indices,
-            cache,
-            infos,
+            colours,
+            centres,
+            transforms,
         }
     }
+}
 
-    pub fn update<'a>(&mut self, texts: impl Iterator<Item = (&'a [u32; 4], impl AsRef<str>)>) {
-        self.cell_rects.buffer.truncate(0);
-        self.glyph_indices.buffer.truncate(0);
-        let mut count = 0;
+pub struct Glyphs {
+    gl: Rc<glow::Context>,
+    obj: Object,
+    quad: Rc<VertexBufferRef>,
+    batches: Vec<Batch>,
 
-        for (rect, text) in texts {
-            let text = text.as_ref();
-            let scale = if text.len() <= 4 {
-                0.4
-            } else if text.len() <= 6 {
-                0.28
-            } else if text.len() <= 8 {
-                0.21
-            } else {
-                0.15
-            };
-            let unit = (rect[2] as f32 * scale) as u32;
-
-            // layout text
-            let mut x_offsets = Vec::new();
-            let mut text_width = 0;
-            for d in text.chars() {
-                let index = self.cache.index_of(d);
-                for _ in 0..4 {
-                    self.glyph_indices.buffer.push(index as u32);
-                }
-
-                x_offsets.push(text_width);
-                let width = {
-                    let glyph = self.infos[index].glyph().unpositioned();
-                    let width = glyph.h_metrics().advance_width;
-                    width / glyph.scale().x * unit as f32
-                };
-                text_width += width as u32;
-            }
+    cache: GlyphCache,
+}
+
+impl Glyphs {
+    pub fn new(gl: Rc<glow::Context>, quad: &Quad, font: Font<'static>) -> anyhow::Result<Self> {
+        let mut program = Program::new(
+            gl.clone(),
+            include_bytes!("../shaders/glyph.v.glsl"),
+            include_bytes!("../shaders/glyph.f.glsl"),
+        )
+        .context("could not build the glyph shaders")?;
+        // without storage buffers, glyph infos come from a uniform buffer
+        program.bind_uniform_block("atlas_t", 0);
+
+        let cache = GlyphCache::new(gl.clone(), font, 0);
+
+        let obj = Object::new(gl.clone(), None, program);
+
+        Ok(Self {
+            gl,
+            obj,
+            quad: quad.vbo(),
+            batches: Vec::new(),
+            cache,
+        })
+    }
+
+    /// Draw texts, using the buffers of the given batch. Drawing the same
+    /// kind of text with the same batch every frame avoids uploads. Texts
+    /// are drawn by increasing depth, like tiles.
+    pub fn update<'a>(&mut self, batch: usize, texts: impl Iterator<Item = &'a Text>) {
+        while self.batches.len() <= batch {
+            self.batches
+                .push(Batch::new(self.gl.clone(), self.quad.clone()));
+        }
+        let batch = &mut self.batches[batch];
+        batch.cell_rects.buffer.truncate(0);
+        batch.glyph_indices.buffer.truncate(0);
+        batch.colours.buffer.truncate(0);
+        batch.centres.buffer.truncate(0);
+        batch.transforms.buffer.truncate(0);
+        let mut count = 0;
 
-            let margin = (
-                ((rect[2] as i32 - text_width as i32) / 2).max(0) as u32,
-                (rect[3] - unit) / 2,
-            );
-
-            for i in 0..text.len() {
-                let x = rect[0] + margin.0 + x_offsets[i];
-                let y = rect[1] + margin.1;
-                for _ in 0..4 {
-                    self.cell_rects
-                        .buffer
-                        .extend_from_slice(&[x, y, unit as u32, unit as u32]);
-                }
+        let texts = by_depth(texts);
+        self.cache
+            .prepare(texts.iter().flat_map(|text| text.text.chars()));
+
+        for text in texts {
+            let chars = layout(self.cache.font(), &text.text, text.rect, &text.style);
+            let (x, y) = display_list::centre(text.rect);
+            let t = &text.transform;
+            for placed in chars {
+                let index = self.cache.index_of(placed.c);
+                batch.glyph_indices.buffer.push(index as u32);
+                batch.cell_rects.buffer.extend_from_slice(&placed.cell());
+                batch.colours.buffer.extend_from_slice(&text.colour);
+                batch.centres.buffer.extend_from_slice(&[x, y]);
+                batch
+                    .transforms
+                    .buffer
+                    .extend_from_slice(&[t.scale, t.rotation, t.opacity]);
                 count += 1;
             }
         }
 
-        self.cell_rects.update(glow::STATIC_DRAW);
-        self.glyph_indices.update(glow::STATIC_DRAW);
-        self.quad.borrow_mut().ensure(count);
-        self.obj.render(count);
+        batch.cell_rects.update(glow::DYNAMIC_DRAW);
+        batch.glyph_indices.update(glow::DYNAMIC_DRAW);
+        batch.colours.update(glow::DYNAMIC_DRAW);
+        batch.centres.update(glow::DYNAMIC_DRAW);
+        batch.transforms.update(glow::DYNAMIC_DRAW);
+        let _tex = self.cache.texture().bind();
+        self.obj.render(&batch.vao, count);
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -0,0 +1,370 @@
+//! Golden-image tests: known boards are rendered offscreen, with GL and with
+//! the software rasterizer, and compared with the PNGs in `tests/golden`. On
+//! a mismatch, the rendered image and a diff, with differing pixels in red,
+//! are written to `target/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the images rendered with GL as the new
+//! references.
+
+use crate::animation::{Progress, Timeline};
+use crate::config::Config;
+use crate::display_list::{self, DisplayList, Overlay, Transform};
+use crate::game::{Direction, Game};
+use crate::headless::Headless;
+use crate::hud::Hud;
+use crate::image::Image;
+use crate::layout::Layout;
+use crate::raster::Rasterizer;
+use crate::scene::Scene;
+use crate::settings::Settings;
+use crate::state::State;
+use crate::stats::Stats;
+use crate::theme::Theme;
+use crate::ui::Status;
+use anyhow::Context;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Largest difference in any channel for pixels to be considered equal,
+/// e.g. from rounding in another GL driver.
+const CHANNEL_TOLERANCE: u8 = 2;
+
+/// Fraction of the pixels that may differ, e.g. on antialiased edges.
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+fn load_png(path: &Path) -> anyhow::Result<Image> {
+    let load = || -> anyhow::Result<Image> {
+        let decoder = png::Decoder::new(File::open(path)?);
+        let mut reader = decoder.read_info()?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels)?;
+        anyhow::ensure!(
+            info.color_type == png::ColorType::Rgba && info.bit_depth == png::BitDepth::Eight,
+            "expected 8-bit RGBA, got {:?} {:?}",
+            info.bit_depth,
+            info.color_type
+        );
+        pixels.truncate(info.buffer_size());
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    };
+    load().with_context(|| format!("could not read {}", path.display()))
+}
+
+/// Number of pixels that differ by more than the tolerance, and an image of
+/// the expected pixels, faded, with differing ones in red.
+fn compare(expected: &Image, actual: &Image) -> (usize, Image) {
+    let mut mismatched = 0;
+    let pixels = expected
+        .pixels
+        .chunks_exact(4)
+        .zip(actual.pixels.chunks_exact(4))
+        .flat_map(|(e, a)| {
+            let same = e
+                .iter()
+                .zip(a)
+                .all(|(e, a)| e.abs_diff(*a) <= CHANNEL_TOLERANCE);
+            if same {
+                let luma = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 12;
+                [luma as u8; 3].into_iter().chain([255])
+            } else {
+                mismatched += 1;
+                [255, 0, 0].into_iter().chain([255])
+            }
+        })
+        .collect();
+    let diff = Image {
+        width: expected.width,
+        height: expected.height,
+        pixels,
+    };
+    (mismatched, diff)
+}
+
+/// Compare an image drawn by `renderer` with the golden image `name`.
+fn check(name: &str, renderer: &str, actual: &Image) {
+    // Thumbnails and screenshots are read back as they are, so edges
+    // blended over the clear colour have to stay opaque.
+    let transparent = actual
+        .pixels
+        .chunks_exact(4)
+        .filter(|p| p[3] != 255)
+        .count();
+    assert_eq!(
+        0, transparent,
+        "{} ({}): pixels are not opaque",
+        name, renderer
+    );
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let golden = root.join("tests/golden").join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save_png(&golden).unwrap();
+        return;
+    }
+
+    let out = root.join("target/golden");
+    std::fs::create_dir_all(&out).unwrap();
+    let write_actual = || {
+        let path: PathBuf = out.join(format!("{}.{}.png", name, renderer));
+        actual.save_png(&path).unwrap();
+        path
+    };
+
+    let expected = match load_png(&golden) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "{:#}\nrendered image: {}\nrun with UPDATE_GOLDEN=1 to accept it",
+            e,
+            write_actual().display()
+        ),
+    };
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        panic!(
+            "{}: expected {}x{}, rendered {}x{} in {}",
+            name,
+            expected.width,
+            expected.height,
+            actual.width,
+            actual.height,
+            write_actual().display()
+        );
+    }
+
+    let (mismatched, diff) = compare(&expected, actual);
+    let allowed = (PIXEL_TOLERANCE * (actual.width * actual.height) as f64) as usize;
+    if mismatched > allowed {
+        let diff_path = out.join(format!("{}.{}.diff.png", name, renderer));
+        diff.save_png(&diff_path).unwrap();
+        panic!(
+            "{} ({}): {} pixels differ, at most {} allowed\nrendered image: {}\ndiff: {}",
+            name,
+            renderer,
+            mismatched,
+            allowed,
+            write_actual().display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// What to draw, besides the board.
+#[derive(Clone, Copy)]
+struct Frame<'a> {
+    /// Board the game is animating towards, and time since the move.
+    target: Option<(&'a Game, Duration)>,
+    state: State,
+}
+
+impl Frame<'_> {
+    const AT_REST: Frame<'static> = Frame {
+        target: None,
+        state: State::Playing,
+    };
+}
+
+/// Display list of a frame as the window would show it.
+fn display_list(size: (u32, u32), game: &Game, frame: Frame) -> DisplayList {
+    let config = Config::default();
+    let theme = Theme::load(&config.theme).unwrap();
+    let layout = Layout::compute(size.0, size.1, game.width(), game.height());
+    let hud = Hud {
+        score: game.score,
+        best: 2048,
+        moves: game.moves,
+        time: Duration::from_secs(83),
+        mode: crate::hud::mode(game.width(), game.height()),
+    };
+    let widgets = frame.state.widgets(
+        &layout,
+        &hud,
+        &Settings::from_config(&config),
+        &Stats::default(),
+    );
+    let overlay = frame.state.title().map(Overlay::menu);
+    let timeline = Timeline::from_config(&config);
+    let (target, progress) = match frame.target {
+        Some((target, elapsed)) => (Some(target), timeline.progress(elapsed)),
+        None => (None, Progress::DONE),
+    };
+
+    display_list::build(&display_list::Frame {
+        layout: &layout,
+        theme: &theme,
+        radius: config.tile_radius,
+        game,
+        target,
+        progress,
+        overlay: overlay.as_ref(),
+        widgets: widgets.iter().map(|w| (w, Status::Normal, false)).collect(),
+    })
+}
+
+/// Draw a display list with GL, or `None` if no offscreen context can be
+/// created, in which case the check is skipped.
+fn render(size: (u32, u32), list: &DisplayList) -> Option<Image> {
+    let headless = match Headless::new(size.0, size.1) {
+        Ok(headless) => headless,
+        Err(e) => {
+            eprintln!("skipped, no offscreen context: {:#}", e);
+            return None;
+        }
+    };
+
+    let config = Config::default();
+    let theme = Theme::load(&config.theme).unwrap();
+    let mut scene = Scene::new(headless.gl(), &config, theme, crate::font::fallback()).unwrap();
+    scene.resize(size.0, size.1);
+    let image = headless.render(|| {
+        scene.clear();
+        scene.draw(list);
+    });
+    drop(scene);
+    Some(image)
+}
+
+/// Compare a frame, drawn both with GL and with the software rasterizer,
+/// with the golden image `name`.
+fn check_frame(name: &str, size: (u32, u32), game: &Game, frame: Frame) {
+    check_list(name, size, &display_list(size, game, frame));
+}
+
+fn check_list(name: &str, size: (u32, u32), list: &DisplayList) {
+    if let Some(image) = render(size, list) {
+        check(name, "gl", &image);
+    }
+    // references are only written from GL, the software rasterizer must
+    // match them too
+    if std::env::var_os("UPDATE_GOLDEN").is_none() {
+        let mut raster = Rasterizer::new(crate::font::fallback());
+        check(name, "software", &raster.render(list, size.0, size.1));
+    }
+}
+
+/// A board with the given tiles, listed bottom row first, 0 for empty.
+fn board(width: usize, height: usize, tiles: &[u8]) -> Game {
+    let mut game = Game::new(width, height);
+    for (i, &v) in tiles.iter().enumerate() {
+        game.tiles[i] = (v > 0).then_some(v);
+    }
+    game.assign_ids();
+    game.score = 1234;
+    game.moves = 56;
+    game
+}
+
+#[test]
+fn compare_tolerance() {
+    let image = |pixels: Vec<u8>| Image {
+        width: 2,
+        height: 1,
+        pixels,
+    };
+    let expected = image(vec![10, 20, 30, 255, 10, 20, 30, 255]);
+    let actual = image(vec![12, 18, 30, 255, 10, 20, 40, 255]);
+    let (mismatched, diff) = compare(&expected, &actual);
+    assert_eq!(1, mismatched);
+    assert_eq!([5, 5, 5, 255, 255, 0, 0, 255], diff.pixels[..]);
+}
+
+#[test]
+fn board_at_rest() {
+    let game = board(4, 4, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0, 0, 1, 0, 2]);
+    check_frame("board_at_rest", (400, 300), &game, Frame::AT_REST);
+}
+
+#[test]
+fn tall_board() {
+    let game = board(3, 5, &[1, 0, 0, 2, 2, 0, 12, 0, 3, 0, 0, 0, 17, 0, 1]);
+    check_frame("tall_board", (300, 400), &game, Frame::AT_REST);
+}
+
+#[test]
+fn slide() {
+    let game = board(4, 4, &[1, 1, 0, 2, 0, 3, 0, 0, 0, 0, 0, 0, 4, 0, 0, 4]);
+    let mut target = game.clone();
+    target.step(Direction::W);
+    let halfway = Timeline::from_config(&Config::default()).slide.duration / 2;
+    for (name, elapsed) in [("slide_start", Duration::ZERO), ("slide_middle", halfway)] {
+        let frame = Frame {
+            target: Some((&target, elapsed)),
+            ..Frame::AT_REST
+        };
+        check_frame(name, (400, 300), &game, frame);
+    }
+}
+
+#[test]
+fn merge_and_spawn() {
+    let game = board(4, 4, &[1, 1, 0, 2, 0, 3, 0, 0, 0, 0, 0, 0, 4, 0, 0, 4]);
+    let mut target = game.clone();
+    target.step(Direction::W);
+    target.tiles[15] = Some(1);
+    target.assign_ids();
+    let timeline = Timeline::from_config(&Config::default());
+    // halfway through the pulse of merged tiles
+    let elapsed = timeline.merge.delay + timeline.merge.duration / 2;
+    let frame = Frame {
+        target: Some((&target, elapsed)),
+        ..Frame::AT_REST
+    };
+    check_frame("merge_and_spawn", (400, 300), &game, frame);
+}
+
+#[test]
+fn main_menu() {
+    let game = board(4, 4, &[1, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let frame = Frame {
+        state: State::Menu,
+        ..Frame::AT_REST
+    };
+    check_frame("main_menu", (400, 300), &game, frame);
+}
+
+#[test]
+fn settings() {
+    let game = Game::new(4, 4);
+    let frame = Frame {
+        state: State::Settings { paused: false },
+        ..Frame::AT_REST
+    };
+    check_frame("settings", (600, 450), &game, frame);
+}
+
+#[test]
+fn transformed_tiles() {
+    let game = board(4, 4, &[1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut list = display_list((400, 300), &game, Frame::AT_REST);
+    let layer = &mut list.layers[display_list::STATIONARY];
+    let transforms = [
+        // turned, and drawn over its neighbour
+        Transform {
+            scale: 1.2,
+            rotation: 0.3,
+            depth: 1.0,
+            ..Transform::IDENTITY
+        },
+        Transform {
+            rotation: -std::f32::consts::FRAC_PI_4,
+            ..Transform::IDENTITY
+        },
+        Transform {
+            scale: 0.6,
+            opacity: 0.5,
+            ..Transform::IDENTITY
+        },
+        Transform {
+            opacity: 0.0,
+            ..Transform::IDENTITY
+        },
+    ];
+    for (i, transform) in transforms.into_iter().enumerate() {
+        layer.tiles[i].transform = transform;
+        layer.texts[i].transform = transform;
+    }
+    check_list("transformed_tiles", (400, 300), &list);
+}
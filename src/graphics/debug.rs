@@ -0,0 +1,57 @@
+use glow::HasContext;
+
+/// Print messages of the driver to stderr, if the context supports
+/// `KHR_debug`. Returns whether it does.
+pub fn enable_debug_output(gl: &mut glow::Context) -> bool {
+    if !gl.supports_debug() {
+        return false;
+    }
+    unsafe {
+        gl.enable(glow::DEBUG_OUTPUT);
+        gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl.debug_message_callback(|source, ty, id, severity, message| {
+            eprintln!(
+                "GL {} {} {} ({}): {}",
+                severity_name(severity),
+                source_name(source),
+                type_name(ty),
+                id,
+                message
+            );
+        });
+    }
+    true
+}
+
+fn severity_name(severity: u32) -> &'static str {
+    match severity {
+        glow::DEBUG_SEVERITY_HIGH => "high",
+        glow::DEBUG_SEVERITY_MEDIUM => "medium",
+        glow::DEBUG_SEVERITY_LOW => "low",
+        glow::DEBUG_SEVERITY_NOTIFICATION => "notification",
+        _ => "unknown",
+    }
+}
+
+fn source_name(source: u32) -> &'static str {
+    match source {
+        glow::DEBUG_SOURCE_API => "api",
+        glow::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        glow::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        glow::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        glow::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+fn type_name(ty: u32) -> &'static str {
+    match ty {
+        glow::DEBUG_TYPE_ERROR => "error",
+        glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        glow::DEBUG_TYPE_PORTABILITY => "portability",
+        glow::DEBUG_TYPE_PERFORMANCE => "performance",
+        glow::DEBUG_TYPE_MARKER => "marker",
+        _ => "other",
+    }
+}
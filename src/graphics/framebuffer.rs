@@ -0,0 +1,113 @@
+use anyhow::bail;
+use glow::HasContext;
+use std::rc::Rc;
+
+/// Storage for a framebuffer attachment that is not sampled from.
+pub struct Renderbuffer {
+    gl: Rc<glow::Context>,
+    inner: glow::NativeRenderbuffer,
+}
+
+impl Renderbuffer {
+    pub fn new(
+        gl: Rc<glow::Context>,
+        format: u32,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<Self> {
+        let inner = unsafe {
+            let rb = gl.create_renderbuffer().map_err(anyhow::Error::msg)?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(rb));
+            gl.renderbuffer_storage(glow::RENDERBUFFER, format, width as i32, height as i32);
+            gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+            rb
+        };
+        Ok(Self { gl, inner })
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_renderbuffer(self.inner) };
+    }
+}
+
+/// A framebuffer object, to render somewhere else than the window.
+pub struct Framebuffer {
+    gl: Rc<glow::Context>,
+    inner: glow::NativeFramebuffer,
+}
+
+impl Framebuffer {
+    pub fn new(gl: Rc<glow::Context>) -> anyhow::Result<Self> {
+        let inner = unsafe { gl.create_framebuffer().map_err(anyhow::Error::msg)? };
+        Ok(Self { gl, inner })
+    }
+
+    /// Attach a renderbuffer at `attachment`, e.g. `COLOR_ATTACHMENT0`, and
+    /// check that the framebuffer can be rendered to.
+    pub fn attach(&self, attachment: u32, renderbuffer: &Renderbuffer) -> anyhow::Result<()> {
+        let _fb = self.bind();
+        let status = unsafe {
+            self.gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                attachment,
+                glow::RENDERBUFFER,
+                Some(renderbuffer.inner),
+            );
+            self.gl.check_framebuffer_status(glow::FRAMEBUFFER)
+        };
+        if status != glow::FRAMEBUFFER_COMPLETE {
+            bail!("incomplete framebuffer (status {:#x})", status);
+        }
+        Ok(())
+    }
+
+    /// Bind the framebuffer, which stays the target of draws and reads until
+    /// the guard is dropped.
+    pub fn bind<'a>(&'a self) -> BoundFramebuffer<'a> {
+        BoundFramebuffer::new(&self.gl, self)
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_framebuffer(self.inner) };
+    }
+}
+
+pub struct BoundFramebuffer<'a> {
+    gl: &'a glow::Context,
+}
+
+impl<'a> BoundFramebuffer<'a> {
+    fn new(gl: &'a glow::Context, framebuffer: &'a Framebuffer) -> Self {
+        unsafe { gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer.inner)) };
+        Self { gl }
+    }
+}
+
+impl Drop for BoundFramebuffer<'_> {
+    fn drop(&mut self) {
+        unsafe { self.gl.bind_framebuffer(glow::FRAMEBUFFER, None) };
+    }
+}
+
+/// Read RGBA pixels of the framebuffer bound for reading, the window if
+/// none is. Rows are returned bottom to top, as GL stores them.
+pub fn read_pixels(gl: &glow::Context, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    unsafe {
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.read_pixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(Some(&mut pixels)),
+        );
+    }
+    pixels
+}
@@ -0,0 +1,68 @@
+use glow::HasContext;
+
+/// The shading language dialect used for a GL context. Shader sources have
+/// no `#version` line: the header of the dialect is prepended at runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Glsl {
+    /// OpenGL 4.3 and later, with shader storage buffers.
+    Core430,
+    /// OpenGL 3.3 to 4.2.
+    Core330,
+    /// OpenGL ES 3.0 and later.
+    Es300,
+}
+
+impl Glsl {
+    pub fn of(gl: &glow::Context) -> Self {
+        Self::from_version(gl.version())
+    }
+
+    fn from_version(version: &glow::Version) -> Self {
+        if version.is_embedded {
+            Glsl::Es300
+        } else if (version.major, version.minor) >= (4, 3) {
+            Glsl::Core430
+        } else {
+            Glsl::Core330
+        }
+    }
+
+    /// Lines to put before the source of every shader. `STORAGE_BUFFER` is
+    /// defined when shader storage buffers are available.
+    pub fn header(self) -> &'static str {
+        match self {
+            Glsl::Core430 => "#version 430 core\n#define STORAGE_BUFFER\n",
+            Glsl::Core330 => "#version 330 core\n",
+            Glsl::Es300 => "#version 300 es\nprecision highp float;\nprecision highp int;\n",
+        }
+    }
+
+    /// Whether shaders can read shader storage buffers. Otherwise, tables
+    /// are passed in uniform buffers, of limited size.
+    pub fn storage_buffers(self) -> bool {
+        self == Glsl::Core430
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u32, minor: u32, is_embedded: bool) -> glow::Version {
+        glow::Version {
+            major,
+            minor,
+            is_embedded,
+            revision: None,
+            vendor_info: String::new(),
+        }
+    }
+
+    #[test]
+    fn from_version() {
+        assert_eq!(Glsl::Core430, Glsl::from_version(&version(4, 6, false)));
+        assert_eq!(Glsl::Core330, Glsl::from_version(&version(4, 1, false)));
+        assert_eq!(Glsl::Core330, Glsl::from_version(&version(3, 3, false)));
+        assert_eq!(Glsl::Es300, Glsl::from_version(&version(3, 2, true)));
+    }
+}
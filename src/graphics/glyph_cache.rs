@@ -1,71 +1,117 @@
-use crate::graphics::{BoundTexture, ShaderStorageBuffer, Texture};
+use crate::graphics::{Glsl, IndexedBuffer, Texture};
+use crate::text::GLYPH_SCALE;
 use glow::HasContext;
-use rusttype::gpu_cache::Cache;
+use rusttype::gpu_cache::{Cache, CacheWriteErr};
 use rusttype::{point, Font, Point, PositionedGlyph, Rect, Scale};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 const MAIN_FONT_ID: usize = 0;
 
+/// Size in bytes of a `GlyphInfo` in the info buffer.
+const INFO_SIZE: usize = 32;
+
 /// Per-glyph information sent to the GPU
-#[derive(Debug)]
-pub struct GlyphInfo<'a> {
-    #[allow(dead_code)]
-    glyph: PositionedGlyph<'a>,
+#[derive(Debug, PartialEq)]
+struct GlyphInfo {
     uv_rect: Rect<f32>,
     rect: Rect<f32>,
 }
 
-impl<'a> GlyphInfo<'a> {
-    pub fn write_to(&self, out: &mut Vec<u8>) {
-        fn write_rect<T>(rect: &Rect<T>, out: &mut Vec<u8>)
+impl GlyphInfo {
+    fn write_to(&self, out: &mut [u8]) {
+        fn write_rect<T>(rect: &Rect<T>, out: &mut [u8])
         where
             T: std::ops::Sub<Output = T>,
             T: Copy,
             T: bytemuck::Pod,
         {
-            out.extend_from_slice(bytemuck::bytes_of(&rect.min.x));
-            out.extend_from_slice(bytemuck::bytes_of(&rect.min.y));
-            out.extend_from_slice(bytemuck::bytes_of(&rect.width()));
-            out.extend_from_slice(bytemuck::bytes_of(&rect.height()));
+            let values = [rect.min.x, rect.min.y, rect.width(), rect.height()];
+            out.copy_from_slice(bytemuck::cast_slice(&values));
         }
 
-        write_rect(&self.uv_rect, out);
-        write_rect(&self.rect, out);
+        write_rect(&self.uv_rect, &mut out[..INFO_SIZE / 2]);
+        write_rect(&self.rect, &mut out[INFO_SIZE / 2..INFO_SIZE]);
     }
+}
 
-    pub fn glyph(&self) -> &PositionedGlyph<'a> {
-        &self.glyph
-    }
+/// A glyph with an assigned slot in the info buffer.
+struct Entry {
+    glyph: PositionedGlyph<'static>,
+    slot: usize,
+    info: Option<GlyphInfo>,
+    /// Last call to `prepare` that used the glyph.
+    used: u64,
 }
 
 /// A cache of glyphs to be passed to the GPU.
+///
+/// Glyphs are rasterized into the atlas texture on demand. Each character is
+/// assigned a slot in an info buffer holding its position in the atlas,
+/// which is updated whenever the glyph is moved or evicted. The info buffer
+/// is a shader storage buffer of up to `MAX_STORAGE_GLYPHS` slots if the
+/// context supports them, and otherwise a uniform buffer of
+/// `MAX_UNIFORM_GLYPHS` slots. When it is full, the slots of the least
+/// recently used characters are given to new ones.
 pub struct GlyphCache {
     gl: Rc<glow::Context>,
     font: Font<'static>,
     cache: Cache<'static>,
     scale: Scale,
-    buffer: ShaderStorageBuffer,
+    texture: Texture,
+    size: u32,
+    buffer: IndexedBuffer,
+    /// Number of slots of the info buffer.
+    max_slots: usize,
+    /// Slots below `slots` that no character uses.
+    free: Vec<usize>,
+    /// Number of slots used so far.
+    slots: usize,
+    /// Number of calls to `prepare`.
+    frame: u64,
+    /// Copy of the contents of the info buffer.
+    data: Vec<u8>,
+    /// Size of the info buffer on the GPU.
+    capacity: usize,
+    entries: HashMap<char, Entry>,
 }
 
 impl GlyphCache {
-    const WIDTH: u32 = 1024;
-    const HEIGHT: u32 = 1024;
-    const SCALE: f32 = 100.0;
-
-    pub fn new(gl: Rc<glow::Context>, index: u32) -> Self {
-        let data =
-            std::fs::read("/usr/share/fonts/TTF/Hack-Bold.ttf").expect("Could not read font");
-        let font: Font<'static> = Font::try_from_vec(data).expect("Error loading font");
+    const INITIAL_SIZE: u32 = 1024;
+    const MAX_SIZE: u32 = 4096;
+    /// Slots of the info buffer when it is a uniform buffer, whose size is
+    /// only guaranteed to reach 16 kB. More distinct characters than this
+    /// cannot be drawn at once.
+    pub const MAX_UNIFORM_GLYPHS: usize = 512;
+    /// Slots of the info buffer when it is a storage buffer.
+    pub const MAX_STORAGE_GLYPHS: usize = 4096;
+
+    pub fn new(gl: Rc<glow::Context>, font: Font<'static>, index: u32) -> Self {
         let scale = Scale {
-            x: Self::SCALE,
-            y: Self::SCALE,
+            x: GLYPH_SCALE,
+            y: GLYPH_SCALE,
         };
 
-        let cache = Cache::builder()
-            .dimensions(Self::WIDTH, Self::HEIGHT)
-            .build();
+        let size = Self::INITIAL_SIZE;
+        let cache = Cache::builder().dimensions(size, size).build();
+        let texture = Self::make_texture(&gl, size);
 
-        let buffer = ShaderStorageBuffer::new(gl.clone());
+        let (mut buffer, max_slots, capacity) = if Glsl::of(&gl).storage_buffers() {
+            (
+                IndexedBuffer::new(gl.clone(), glow::SHADER_STORAGE_BUFFER),
+                Self::MAX_STORAGE_GLYPHS,
+                0,
+            )
+        } else {
+            let max = Self::MAX_UNIFORM_GLYPHS;
+            // a uniform buffer must hold the whole block declared by the shader
+            (
+                IndexedBuffer::new(gl.clone(), glow::UNIFORM_BUFFER),
+                max,
+                max * INFO_SIZE,
+            )
+        };
+        buffer.set_data(&vec![0; capacity]);
         buffer.bind(index);
 
         Self {
@@ -73,13 +119,138 @@ impl GlyphCache {
             font,
             cache,
             scale,
+            texture,
+            size,
             buffer,
+            max_slots,
+            free: Vec::new(),
+            slots: 0,
+            frame: 0,
+            data: Vec::new(),
+            capacity,
+            entries: HashMap::new(),
         }
     }
 
-    pub fn upload_atlas(&mut self, _tex: &BoundTexture) -> () {
-        self.cache
-            .cache_queued(|rect, data| unsafe {
+    fn make_texture(gl: &Rc<glow::Context>, size: u32) -> Texture {
+        let texture = Texture::new(gl.clone(), size, size);
+        unsafe { gl.bind_texture(glow::TEXTURE_2D, None) };
+        texture
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Index of the glyph for the given character in the info buffer. The
+    /// character must have been passed to `prepare`.
+    pub fn index_of(&self, c: char) -> usize {
+        self.entries[&c].slot
+    }
+
+    pub fn font(&self) -> &Font<'static> {
+        &self.font
+    }
+
+    /// Make sure the glyphs for all the given characters are in the atlas,
+    /// and that their entries in the info buffer are up to date. When the
+    /// info buffer is full, characters not passed here may lose their slot,
+    /// which leaves their indices invalid.
+    pub fn prepare(&mut self, chars: impl Iterator<Item = char>) {
+        let mut queued = Vec::new();
+        for c in chars {
+            if !queued.contains(&c) {
+                queued.push(c);
+            }
+        }
+        self.frame += 1;
+        self.evict(&queued);
+
+        for &c in queued.iter() {
+            if !self.entries.contains_key(&c) {
+                let slot = self.free.pop().unwrap_or_else(|| {
+                    self.slots += 1;
+                    self.slots - 1
+                });
+                let glyph = self
+                    .font
+                    .glyph(c)
+                    .scaled(self.scale)
+                    .positioned(point(0.0, 0.0));
+                let entry = Entry {
+                    glyph,
+                    slot,
+                    info: None,
+                    used: 0,
+                };
+                self.entries.insert(c, entry);
+            }
+            let entry = self.entries.get_mut(&c).unwrap();
+            entry.used = self.frame;
+            self.cache.queue_glyph(MAIN_FONT_ID, entry.glyph.clone());
+        }
+
+        self.upload();
+
+        let mut resized = false;
+        for c in queued {
+            let entry = self.entries.get_mut(&c).unwrap();
+            let info = Self::info(&self.cache, &entry.glyph);
+            if entry.info.as_ref() == Some(&info) {
+                continue;
+            }
+
+            let offset = entry.slot * INFO_SIZE;
+            if self.data.len() < offset + INFO_SIZE {
+                self.data.resize(offset + INFO_SIZE, 0);
+            }
+            info.write_to(&mut self.data[offset..offset + INFO_SIZE]);
+            if self.data.len() > self.capacity {
+                resized = true;
+            } else if !resized {
+                self.buffer
+                    .set_sub_data(offset, &self.data[offset..offset + INFO_SIZE]);
+            }
+            entry.info = Some(info);
+        }
+
+        if resized {
+            self.capacity = self.data.len().next_power_of_two();
+            let mut data = self.data.clone();
+            data.resize(self.capacity, 0);
+            self.buffer.set_data(&data);
+        }
+    }
+
+    /// Free the slots of the least recently used characters not in `queued`,
+    /// so that the new characters of `queued` fit in the info buffer.
+    fn evict(&mut self, queued: &[char]) {
+        let new = queued
+            .iter()
+            .filter(|c| !self.entries.contains_key(c))
+            .count();
+        let excess = (self.entries.len() + new).saturating_sub(self.max_slots);
+        if excess == 0 {
+            return;
+        }
+        let mut unused = self
+            .entries
+            .iter()
+            .filter(|(c, _)| !queued.contains(c))
+            .map(|(&c, entry)| (entry.used, c))
+            .collect::<Vec<_>>();
+        unused.sort_unstable();
+        for (_, c) in unused.into_iter().take(excess) {
+            let entry = self.entries.remove(&c).unwrap();
+            self.free.push(entry.slot);
+        }
+    }
+
+    /// Rasterize queued glyphs into the atlas, growing it if they do not fit.
+    fn upload(&mut self) {
+        loop {
+            let _tex = self.texture.bind();
+            let result = self.cache.cache_queued(|rect, data| unsafe {
                 self.gl.tex_sub_image_2d(
                     glow::TEXTURE_2D,
                     0,
@@ -91,86 +262,100 @@ impl GlyphCache {
                     glow::UNSIGNED_BYTE,
                     glow::PixelUnpackData::Slice(Some(data)),
                 );
-            })
-            .unwrap();
-    }
+            });
+            drop(_tex);
 
-    pub fn index_of(&self, c: char) -> usize {
-        c as usize - 0x20
+            match result {
+                Ok(_) => return,
+                Err(CacheWriteErr::NoRoomForWholeQueue) if self.size < Self::MAX_SIZE => {
+                    // all glyphs are cleared from the cache, but remain queued
+                    self.size *= 2;
+                    self.cache
+                        .to_builder()
+                        .dimensions(self.size, self.size)
+                        .rebuild(&mut self.cache);
+                    self.texture = Self::make_texture(&self.gl, self.size);
+                }
+                Err(e) => {
+                    eprintln!("Could not cache glyphs: {}", e);
+                    return;
+                }
+            }
+        }
     }
 
-    pub fn make_atlas(&mut self) -> (Vec<GlyphInfo<'static>>, Texture) {
-        // queue all printable ASCII characters
-        let glyphs = {
-            let mut glyphs = Vec::with_capacity(128);
-            for c in 0x20..0x7f as u8 {
-                let glyph = self
-                    .font
-                    .glyph(c as char)
-                    .scaled(self.scale)
-                    .positioned(point(0.0, 0.0));
-                self.cache.queue_glyph(MAIN_FONT_ID, glyph.clone());
-                glyphs.push(glyph);
-            }
-            glyphs
+    fn info(cache: &Cache<'static>, glyph: &PositionedGlyph<'static>) -> GlyphInfo {
+        let (uv_rect, rect) = cache
+            .rect_for(MAIN_FONT_ID, glyph)
+            .ok()
+            .flatten()
+            .unwrap_or((
+                Rect {
+                    min: point(0.0, 0.0),
+                    max: point(0.0, 0.0),
+                },
+                Rect {
+                    min: point(0, 0),
+                    max: point(0, 0),
+                },
+            ));
+
+        let vmetrics = glyph.font().v_metrics(glyph.scale());
+
+        // scale rect and reposition
+        let rect = Rect {
+            min: Point {
+                x: rect.min.x as f32 / GLYPH_SCALE,
+                y: (-rect.min.y as f32 - vmetrics.descent) / GLYPH_SCALE,
+            },
+            max: Point {
+                x: rect.max.x as f32 / GLYPH_SCALE,
+                y: (-rect.max.y as f32 - vmetrics.descent) / GLYPH_SCALE,
+            },
         };
 
-        let tex = Texture::new(self.gl.clone(), Self::WIDTH, Self::HEIGHT);
-        self.upload_atlas(&tex.bind());
-
-        unsafe { self.gl.bind_texture(glow::TEXTURE_2D, None) };
-
-        // populate info array
-        let infos = {
-            let mut infos: Vec<GlyphInfo> = Vec::with_capacity(glyphs.len());
-            for glyph in glyphs {
-                let (uv_rect, rect) = self
-                    .cache
-                    .rect_for(MAIN_FONT_ID, &glyph)
-                    .unwrap()
-                    .unwrap_or((
-                        Rect {
-                            min: point(0.0, 0.0),
-                            max: point(0.0, 0.0),
-                        },
-                        Rect {
-                            min: point(0, 0),
-                            max: point(0, 0),
-                        },
-                    ));
-
-                let vmetrics = glyph.font().v_metrics(self.scale);
-
-                // scale rect and reposition
-                let rect = Rect {
-                    min: Point {
-                        x: rect.min.x as f32 / Self::SCALE,
-                        y: (-rect.min.y as f32 - vmetrics.descent) / Self::SCALE,
-                    },
-                    max: Point {
-                        x: rect.max.x as f32 / Self::SCALE,
-                        y: (-rect.max.y as f32 - vmetrics.descent) / Self::SCALE,
-                    },
-                };
+        GlyphInfo { uv_rect, rect }
+    }
+}
 
-                infos.push(GlyphInfo {
-                    glyph,
-                    uv_rect,
-                    rect,
-                })
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::Headless;
 
-            infos
+    #[test]
+    fn overflow() {
+        let headless = match Headless::new(1, 1) {
+            Ok(headless) => headless,
+            Err(e) => {
+                eprintln!("skipped, no offscreen context: {:#}", e);
+                return;
+            }
         };
+        let mut cache = GlyphCache::new(headless.gl(), crate::font::fallback(), 0);
+        let max = cache.max_slots;
 
-        {
-            let mut data = Vec::new();
-            for info in infos.iter() {
-                info.write_to(&mut data);
-            }
-            self.buffer.set_data(&data);
+        // many more distinct characters than slots, a few at a time
+        let chars =
+            |frame: u32| (0..100).filter_map(move |i| char::from_u32(0x4e00 + frame * 100 + i));
+        let frames = (2 * max / 100) as u32;
+        for frame in 0..frames {
+            cache.prepare(chars(frame));
+            assert!(cache.entries.len() <= max);
         }
+        assert_eq!(max, cache.slots);
 
-        (infos, tex)
+        // the last characters have their own slots, and the next frame only
+        // evicts older ones
+        let last = chars(frames - 1)
+            .map(|c| cache.index_of(c))
+            .collect::<Vec<_>>();
+        assert!(last.iter().all(|&slot| slot < max));
+        let mut distinct = last.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(last.len(), distinct.len());
+        cache.prepare(chars(frames));
+        assert!(chars(frames - 1).all(|c| cache.entries.contains_key(&c)));
     }
 }
@@ -0,0 +1,53 @@
+use glow::HasContext;
+use std::rc::Rc;
+
+/// A buffer bound to an indexed target, i.e. a shader storage buffer or a
+/// uniform buffer.
+pub struct IndexedBuffer {
+    gl: Rc<glow::Context>,
+    target: u32,
+    inner: glow::NativeBuffer,
+}
+
+impl IndexedBuffer {
+    pub fn new(gl: Rc<glow::Context>, target: u32) -> Self {
+        let buffer = unsafe { gl.create_buffer().unwrap() };
+        Self {
+            gl,
+            target,
+            inner: buffer,
+        }
+    }
+
+    pub fn set_data(&mut self, data: &[u8]) {
+        unsafe {
+            self.gl.bind_buffer(self.target, Some(self.inner));
+            self.gl
+                .buffer_data_u8_slice(self.target, data, glow::STATIC_DRAW);
+            self.gl.bind_buffer(self.target, None);
+        }
+    }
+
+    /// Overwrite part of the buffer, which must be large enough already.
+    pub fn set_sub_data(&mut self, offset: usize, data: &[u8]) {
+        unsafe {
+            self.gl.bind_buffer(self.target, Some(self.inner));
+            self.gl
+                .buffer_sub_data_u8_slice(self.target, offset as i32, data);
+            self.gl.bind_buffer(self.target, None);
+        }
+    }
+
+    pub fn bind(&self, index: u32) {
+        unsafe {
+            self.gl
+                .bind_buffer_base(self.target, index, Some(self.inner));
+        }
+    }
+}
+
+impl Drop for IndexedBuffer {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_buffer(self.inner) };
+    }
+}
@@ -1,20 +1,25 @@
-mod element_buffer;
+mod debug;
+mod framebuffer;
+mod glsl;
 mod glyph_cache;
+mod indexed_buffer;
 mod object;
 pub mod quad;
 mod shader;
-mod ss_buffer;
 mod texture;
 mod uniform;
 pub mod util;
 mod vertex_array;
 mod vertex_buffer;
 
-pub use glyph_cache::{GlyphCache, GlyphInfo};
+pub use debug::enable_debug_output;
+pub use framebuffer::{read_pixels, Framebuffer, Renderbuffer};
+pub use glsl::Glsl;
+pub use glyph_cache::GlyphCache;
+pub use indexed_buffer::IndexedBuffer;
 pub use object::Object;
 pub use quad::Quad;
 pub use shader::Program;
-pub use ss_buffer::ShaderStorageBuffer;
-pub use texture::{BoundTexture, Texture};
+pub use texture::Texture;
 pub use vertex_array::VertexArray;
-pub use vertex_buffer::VertexBuffer;
+pub use vertex_buffer::{take_uploaded, VertexBuffer, VertexBufferRef};
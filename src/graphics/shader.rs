@@ -1,44 +1,86 @@
-use super::uniform::UniformValue;
+use super::glsl::Glsl;
+use super::uniform::{Uniform, UniformValue};
+use anyhow::{bail, Context};
 use glow::HasContext;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
+/// Location and latest value of a uniform.
+struct Slot {
+    loc: Option<glow::NativeUniformLocation>,
+    value: Option<Uniform>,
+}
+
 pub struct Program {
     gl: Rc<glow::Context>,
     pub inner: glow::NativeProgram,
+    /// Uniforms by name, with locations looked up on first use.
+    uniforms: HashMap<String, Slot>,
+    /// Uniforms changed since the program was last bound.
+    pending: RefCell<Vec<(glow::NativeUniformLocation, Uniform)>>,
 }
 
 impl Program {
-    pub fn new(gl: Rc<glow::Context>, vert: &[u8], frag: &[u8]) -> Self {
+    /// Compile and link a program, with the header of the GLSL dialect of
+    /// the context prepended to each shader.
+    pub fn new(gl: Rc<glow::Context>, vert: &[u8], frag: &[u8]) -> anyhow::Result<Self> {
+        let header = Glsl::of(&gl).header();
         let prog = unsafe {
-            let vert = compile_shader_from_source(&gl, glow::VERTEX_SHADER, vert);
+            let vert = compile_shader_from_source(&gl, glow::VERTEX_SHADER, header, vert)
+                .context("could not compile the vertex shader")?;
+            let frag = compile_shader_from_source(&gl, glow::FRAGMENT_SHADER, header, frag)
+                .context("could not compile the fragment shader");
+            let frag = match frag {
+                Ok(frag) => frag,
+                Err(e) => {
+                    gl.delete_shader(vert);
+                    return Err(e);
+                }
+            };
 
-            let frag = compile_shader_from_source(&gl, glow::FRAGMENT_SHADER, frag);
+            let program = link_program(&gl, vert, frag);
 
-            let program = gl.create_program().unwrap();
-            gl.attach_shader(program, vert);
-            gl.attach_shader(program, frag);
-            gl.link_program(program);
-            if !gl.get_program_link_status(program) {
-                panic!("link error: {}", gl.get_program_info_log(program));
-            }
-
-            gl.detach_shader(program, vert);
             gl.delete_shader(vert);
-
-            gl.detach_shader(program, frag);
             gl.delete_shader(frag);
-            program
+            program.context("could not link the shaders")?
         };
 
-        Self { gl, inner: prog }
+        Ok(Self {
+            gl,
+            inner: prog,
+            uniforms: HashMap::new(),
+            pending: RefCell::new(Vec::new()),
+        })
     }
 
+    /// Set a uniform. The value is passed to GL when the program is next
+    /// bound, and only if it changed.
     pub fn set_uniform(&mut self, name: &str, value: impl UniformValue) {
+        if !self.uniforms.contains_key(name) {
+            let loc = unsafe { self.gl.get_uniform_location(self.inner, name) };
+            self.uniforms
+                .insert(name.to_string(), Slot { loc, value: None });
+        }
+        let slot = self.uniforms.get_mut(name).unwrap();
+
+        let value = value.uniform();
+        if slot.value == Some(value) {
+            return;
+        }
+        slot.value = Some(value);
+        if let Some(loc) = slot.loc {
+            self.pending.get_mut().push((loc, value));
+        }
+    }
+
+    /// Read the uniform block `name`, if the program has it, from the
+    /// uniform buffer bound at `index`.
+    pub fn bind_uniform_block(&mut self, name: &str, index: u32) {
         unsafe {
-            self.gl.use_program(Some(self.inner));
-            let loc = self.gl.get_uniform_location(self.inner, name);
-            value.set(&self.gl, loc.as_ref());
-            self.gl.use_program(None);
+            if let Some(block) = self.gl.get_uniform_block_index(self.inner, name) {
+                self.gl.uniform_block_binding(self.inner, block, index);
+            }
         }
     }
 
@@ -50,19 +92,43 @@ impl Program {
 fn compile_shader_from_source(
     gl: &glow::Context,
     shader_type: u32,
+    header: &str,
     src: &[u8],
-) -> glow::NativeShader {
+) -> anyhow::Result<glow::NativeShader> {
+    let src = std::str::from_utf8(src).context("shader source is not UTF-8")?;
     unsafe {
-        let shader = gl.create_shader(shader_type).unwrap();
-        gl.shader_source(shader, std::str::from_utf8(src).unwrap());
+        let shader = gl.create_shader(shader_type).map_err(anyhow::Error::msg)?;
+        gl.shader_source(shader, &format!("{}{}", header, src));
         gl.compile_shader(shader);
         if !gl.get_shader_compile_status(shader) {
-            panic!("{}", gl.get_shader_info_log(shader));
+            let log = gl.get_shader_info_log(shader);
+            gl.delete_shader(shader);
+            bail!("{}", log.trim_end());
         }
-        shader
+        Ok(shader)
     }
 }
 
+/// Link a program from compiled shaders, which are detached afterwards.
+unsafe fn link_program(
+    gl: &glow::Context,
+    vert: glow::NativeShader,
+    frag: glow::NativeShader,
+) -> anyhow::Result<glow::NativeProgram> {
+    let program = gl.create_program().map_err(anyhow::Error::msg)?;
+    gl.attach_shader(program, vert);
+    gl.attach_shader(program, frag);
+    gl.link_program(program);
+    gl.detach_shader(program, vert);
+    gl.detach_shader(program, frag);
+    if !gl.get_program_link_status(program) {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        bail!("{}", log.trim_end());
+    }
+    Ok(program)
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
         unsafe { self.gl.delete_program(self.inner) };
@@ -75,7 +141,12 @@ pub struct BoundProgram<'a> {
 
 impl<'a> BoundProgram<'a> {
     fn new(program: &'a Program) -> BoundProgram<'a> {
-        unsafe { program.gl.use_program(Some(program.inner)) };
+        unsafe {
+            program.gl.use_program(Some(program.inner));
+            for (loc, value) in program.pending.borrow_mut().drain(..) {
+                value.set(&program.gl, Some(&loc));
+            }
+        }
         BoundProgram { program }
     }
 }
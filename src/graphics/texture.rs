@@ -25,7 +25,7 @@ impl Texture {
             gl.tex_image_2d(
                 glow::TEXTURE_2D,
                 0,
-                glow::RGBA as i32,
+                glow::R8 as i32,
                 width as i32,
                 height as i32,
                 0,
@@ -40,7 +40,7 @@ impl Texture {
     }
 
     pub fn bind<'a>(&'a self) -> BoundTexture<'a> {
-        BoundTexture::new(&self.gl, &self)
+        BoundTexture::new(&self.gl, self)
     }
 }
 
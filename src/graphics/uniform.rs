@@ -3,18 +3,34 @@ use rusttype::Rect;
 
 type Loc<'a> = Option<&'a glow::NativeUniformLocation>;
 
+/// Value of a uniform, kept until the program is next bound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Uniform {
+    Float(f32),
+    IVec4([i32; 4]),
+}
+
+impl Uniform {
+    pub(super) unsafe fn set(self, gl: &glow::Context, loc: Loc) {
+        match self {
+            Uniform::Float(x) => gl.uniform_1_f32(loc, x),
+            Uniform::IVec4([x, y, z, w]) => gl.uniform_4_i32(loc, x, y, z, w),
+        }
+    }
+}
+
 pub trait UniformValue {
-    unsafe fn set(&self, gl: &glow::Context, loc: Loc);
+    fn uniform(&self) -> Uniform;
 }
 
 impl UniformValue for Rect<i32> {
-    unsafe fn set(&self, gl: &glow::Context, loc: Loc) {
-        gl.uniform_4_i32(loc, self.min.x, self.min.y, self.width(), self.height());
+    fn uniform(&self) -> Uniform {
+        Uniform::IVec4([self.min.x, self.min.y, self.width(), self.height()])
     }
 }
 
 impl UniformValue for f32 {
-    unsafe fn set(&self, gl: &glow::Context, loc: Loc) {
-        gl.uniform_1_f32(loc, *self);
+    fn uniform(&self) -> Uniform {
+        Uniform::Float(*self)
     }
 }
@@ -1,6 +1,7 @@
 use super::vertex_array::BoundVertexArray;
 use glow::HasContext;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub trait GL: bytemuck::Pod {
     fn ty() -> u32;
@@ -29,16 +30,20 @@ pub struct VertexBufferRef {
     inner: glow::NativeBuffer,
     size: i32,
     ty: u32,
+    /// Number of instances sharing each element, or 0 for one element per
+    /// vertex.
+    divisor: u32,
 }
 
 impl VertexBufferRef {
-    fn new(gl: Rc<glow::Context>, size: i32, ty: u32) -> VertexBufferRef {
+    fn new(gl: Rc<glow::Context>, size: i32, ty: u32, divisor: u32) -> VertexBufferRef {
         let vbo = unsafe { gl.create_buffer().unwrap() };
         VertexBufferRef {
             gl,
             inner: vbo,
             size,
             ty,
+            divisor,
         }
     }
 
@@ -54,6 +59,7 @@ impl VertexBufferRef {
                     .vertex_attrib_pointer_f32(i, self.size, self.ty, false, 0, 0),
                 _ => panic!("Unsupported VertexBuffer type {}", self.ty),
             };
+            self.gl.vertex_attrib_divisor(i, self.divisor);
             self.gl.enable_vertex_attrib_array(i);
         }
     }
@@ -75,20 +81,43 @@ impl Drop for VertexBufferRef {
     }
 }
 
-#[derive(Clone)]
+/// Number of bytes uploaded to vertex buffers since the last call to
+/// [`take_uploaded`].
+static UPLOADED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn take_uploaded() -> usize {
+    UPLOADED.swap(0, Ordering::Relaxed)
+}
+
+/// A vertex buffer with a CPU-side copy of its contents. Calling `update`
+/// uploads the contents only if they changed since the last upload, reusing
+/// the GPU storage when it is large enough.
 pub struct VertexBuffer<T> {
     inner: Rc<VertexBufferRef>,
     pub buffer: Vec<T>,
-    phantom: std::marker::PhantomData<T>,
+    /// Contents of the GPU buffer.
+    uploaded: Vec<T>,
+    /// Size of the GPU storage, in bytes.
+    capacity: usize,
 }
 
-impl<T: GL> VertexBuffer<T> {
+impl<T: GL + PartialEq> VertexBuffer<T> {
     pub fn new(gl: Rc<glow::Context>, size: i32) -> Self {
-        let inner = Rc::new(VertexBufferRef::new(gl, size, T::ty()));
+        Self::with_divisor(gl, size, 0)
+    }
+
+    /// A buffer holding one element per instance.
+    pub fn per_instance(gl: Rc<glow::Context>, size: i32) -> Self {
+        Self::with_divisor(gl, size, 1)
+    }
+
+    fn with_divisor(gl: Rc<glow::Context>, size: i32, divisor: u32) -> Self {
+        let inner = Rc::new(VertexBufferRef::new(gl, size, T::ty(), divisor));
         Self {
             inner,
             buffer: Vec::new(),
-            phantom: std::marker::PhantomData,
+            uploaded: Vec::new(),
+            capacity: 0,
         }
     }
 
@@ -96,17 +125,30 @@ impl<T: GL> VertexBuffer<T> {
         self.inner.clone()
     }
 
+    /// Upload the contents of the buffer, if they changed. With a dynamic
+    /// `usage`, the storage is orphaned rather than reallocated, so that a
+    /// draw call still using the old contents does not stall the upload.
     pub fn update(&mut self, usage: u32) {
+        if self.buffer == self.uploaded {
+            return;
+        }
+
+        let data: &[u8] = bytemuck::cast_slice(&self.buffer);
+        let gl = &self.inner.gl;
         unsafe {
-            self.inner
-                .gl
-                .bind_buffer(glow::ARRAY_BUFFER, Some(self.inner.inner));
-            self.inner.gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                bytemuck::cast_slice(&self.buffer),
-                usage,
-            );
-            self.inner.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.inner.inner));
+            if data.len() > self.capacity {
+                self.capacity = data.len().next_power_of_two();
+                gl.buffer_data_size(glow::ARRAY_BUFFER, self.capacity as i32, usage);
+            } else if usage != glow::STATIC_DRAW {
+                gl.buffer_data_size(glow::ARRAY_BUFFER, self.capacity as i32, usage);
+            }
+            gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, data);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
         }
+        UPLOADED.fetch_add(data.len(), Ordering::Relaxed);
+
+        self.uploaded.clear();
+        self.uploaded.extend_from_slice(&self.buffer);
     }
 }
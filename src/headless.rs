@@ -0,0 +1,136 @@
+use crate::graphics::{read_pixels, Framebuffer, Renderbuffer};
+use crate::image::Image;
+use anyhow::{anyhow, Context};
+use glow::HasContext;
+use glutin::api::egl::{context::PossiblyCurrentContext, device::Device, display::Display};
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::ContextAttributesBuilder;
+use glutin::display::{GetGlDisplay, GlDisplay};
+use std::rc::Rc;
+
+/// A GL context without a window, on a surfaceless EGL display such as Mesa's
+/// software renderer, drawing into a framebuffer that can be read back.
+///
+/// GL objects made with `gl()` should be dropped before the context.
+pub struct Headless {
+    gl: Rc<glow::Context>,
+    framebuffer: Framebuffer,
+    _colour: Renderbuffer,
+    width: u32,
+    height: u32,
+    _context: PossiblyCurrentContext,
+}
+
+impl Headless {
+    /// Create a context rendering to an offscreen image of the given size,
+    /// on the first EGL device that provides one.
+    pub fn new(width: u32, height: u32) -> anyhow::Result<Self> {
+        let mut errors = Vec::new();
+        let devices = Device::query_devices().context("could not list EGL devices")?;
+        let context = devices
+            .into_iter()
+            .find_map(|device| {
+                create_context(&device)
+                    .map_err(|e| errors.push(format!("{}: {:#}", device_name(&device), e)))
+                    .ok()
+            })
+            .ok_or_else(|| anyhow!("no EGL device can render offscreen\n{}", errors.join("\n")))?;
+
+        let gl = unsafe {
+            glow::Context::from_loader_function_cstr(|s| context.display().get_proc_address(s))
+        };
+        let gl = Rc::new(gl);
+
+        let framebuffer = Framebuffer::new(gl.clone())?;
+        let colour = Renderbuffer::new(gl.clone(), glow::RGBA8, width, height)?;
+        framebuffer.attach(glow::COLOR_ATTACHMENT0, &colour)?;
+
+        Ok(Self {
+            gl,
+            framebuffer,
+            _colour: colour,
+            width,
+            height,
+            _context: context,
+        })
+    }
+
+    pub fn gl(&self) -> Rc<glow::Context> {
+        self.gl.clone()
+    }
+
+    /// Draw into the offscreen image with `draw`, and read it back.
+    pub fn render(&self, draw: impl FnOnce()) -> Image {
+        let _fb = self.framebuffer.bind();
+        unsafe {
+            self.gl
+                .viewport(0, 0, self.width as i32, self.height as i32)
+        };
+        draw();
+        let pixels = read_pixels(&self.gl, self.width, self.height);
+        Image::from_gl(self.width, self.height, pixels)
+    }
+}
+
+fn device_name(device: &Device) -> String {
+    device
+        .name()
+        .or(device.vendor())
+        .unwrap_or("unknown device")
+        .to_string()
+}
+
+fn create_context(device: &Device) -> anyhow::Result<PossiblyCurrentContext> {
+    let display = unsafe { Display::with_device(device, None)? };
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_surface_type(ConfigSurfaceTypes::empty())
+        .build();
+    let config = unsafe { display.find_configs(template)? }
+        .next()
+        .context("no suitable config")?;
+
+    let mut errors = Vec::new();
+    let context = crate::CONTEXT_APIS
+        .iter()
+        .find_map(|&api| {
+            let attributes = ContextAttributesBuilder::new()
+                .with_context_api(api)
+                .build(None);
+            unsafe { display.create_context(&config, &attributes) }
+                .map_err(|e| errors.push(format!("{:?}: {}", api, e)))
+                .ok()
+        })
+        .with_context(|| format!("could not create a context\n{}", errors.join("\n")))?;
+    Ok(context.make_current_surfaceless()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear() {
+        let headless = match Headless::new(4, 2) {
+            Ok(headless) => headless,
+            Err(e) => {
+                eprintln!("skipped, no offscreen context: {:#}", e);
+                return;
+            }
+        };
+        let gl = headless.gl();
+        let image = headless.render(|| unsafe {
+            gl.clear_color(1.0, 0.0, 0.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            // bottom row, which comes last once read back
+            gl.enable(glow::SCISSOR_TEST);
+            gl.scissor(0, 0, 4, 1);
+            gl.clear_color(0.0, 0.0, 1.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            gl.disable(glow::SCISSOR_TEST);
+        });
+        assert_eq!((4, 2), (image.width, image.height));
+        assert_eq!([255, 0, 0, 255], image.pixels[..4]);
+        assert_eq!([0, 0, 255, 255], image.pixels[28..]);
+    }
+}
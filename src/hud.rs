@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+/// Time spent on the current game, from the first move until it is over.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timer {
+    start: Option<Instant>,
+    stop: Option<Instant>,
+}
+
+impl Timer {
+    /// Start the timer, if it has not been started yet.
+    pub fn start(&mut self, now: Instant) {
+        if self.start.is_none() {
+            self.start = Some(now);
+        }
+    }
+
+    pub fn stop(&mut self, now: Instant) {
+        if self.running() {
+            self.stop = Some(now);
+        }
+    }
+
+    /// Continue counting after the timer was stopped, e.g. when undoing the
+    /// last move of a game.
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(stop) = self.stop.take() {
+            self.start = self.start.map(|start| start + (now - stop));
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn running(&self) -> bool {
+        self.start.is_some() && self.stop.is_none()
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        match self.start {
+            Some(start) => self.stop.unwrap_or(now).saturating_duration_since(start),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// Format a duration as minutes and seconds, with hours when needed.
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Name of the mode of a game, e.g. `4x4`.
+pub fn mode(width: usize, height: usize) -> String {
+    format!("{}x{}", width, height)
+}
+
+/// Information shown in the panel next to the board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hud {
+    pub score: u64,
+    pub best: u64,
+    pub moves: u32,
+    pub time: Duration,
+    pub mode: String,
+}
+
+impl Hud {
+    /// Label and value of each box of the panel.
+    pub fn entries(&self) -> [(&'static str, String); 5] {
+        [
+            ("SCORE", self.score.to_string()),
+            ("BEST", self.best.max(self.score).to_string()),
+            ("MOVES", self.moves.to_string()),
+            ("TIME", format_time(self.time)),
+            ("MODE", self.mode.clone()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer() {
+        let t0 = Instant::now();
+        let secs = |n| t0 + Duration::from_secs(n);
+
+        let mut timer = Timer::default();
+        assert!(!timer.running());
+        assert_eq!(Duration::ZERO, timer.elapsed(secs(5)));
+
+        timer.start(secs(1));
+        timer.start(secs(2));
+        assert!(timer.running());
+        assert_eq!(Duration::from_secs(4), timer.elapsed(secs(5)));
+
+        timer.stop(secs(6));
+        assert!(!timer.running());
+        assert_eq!(Duration::from_secs(5), timer.elapsed(secs(10)));
+
+        timer.resume(secs(10));
+        assert_eq!(Duration::from_secs(6), timer.elapsed(secs(11)));
+
+        timer.reset();
+        assert_eq!(Duration::ZERO, timer.elapsed(secs(12)));
+    }
+
+    #[test]
+    fn time_format() {
+        assert_eq!("0:00", format_time(Duration::ZERO));
+        assert_eq!("1:05", format_time(Duration::from_millis(65_900)));
+        assert_eq!("59:59", format_time(Duration::from_secs(3599)));
+        assert_eq!("2:00:01", format_time(Duration::from_secs(7201)));
+    }
+
+    #[test]
+    fn best_includes_current_score() {
+        let hud = Hud {
+            score: 120,
+            best: 100,
+            moves: 3,
+            time: Duration::ZERO,
+            mode: mode(4, 4),
+        };
+        let entries = hud.entries();
+        assert_eq!(("BEST", "120".to_string()), entries[1]);
+        assert_eq!(("MODE", "4x4".to_string()), entries[4]);
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{ensure, Context};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// An RGBA image, with rows from top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Make an image from pixels read back from GL, whose rows go from bottom
+    /// to top.
+    pub fn from_gl(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        let row = width as usize * 4;
+        let pixels = pixels.chunks_exact(row).rev().flatten().copied().collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn save_png(&self, path: &Path) -> anyhow::Result<()> {
+        let write = || -> anyhow::Result<()> {
+            let file = BufWriter::new(File::create(path)?);
+            let mut encoder = png::Encoder::new(file, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+            writer.finish()?;
+            Ok(())
+        };
+        write().with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// Parse a `WIDTHxHEIGHT` size, e.g. `800x600`.
+pub fn parse_size(s: &str) -> anyhow::Result<(u32, u32)> {
+    let parse = || {
+        let (w, h) = s.split_once('x')?;
+        Some((w.parse().ok()?, h.parse().ok()?))
+    };
+    let (w, h) = parse().with_context(|| format!("invalid size {:?}, expected e.g. 800x600", s))?;
+    ensure!(w > 0 && h > 0, "invalid size {:?}", s);
+    Ok((w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_gl() {
+        let image = Image::from_gl(1, 2, vec![0, 0, 0, 255, 1, 1, 1, 255]);
+        assert_eq!(vec![1, 1, 1, 255, 0, 0, 0, 255], image.pixels);
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!((800, 600), parse_size("800x600").unwrap());
+        assert!(parse_size("800").is_err());
+        assert!(parse_size("0x600").is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::game::Direction;
+use std::collections::VecDeque;
+
+pub fn default_queue_size() -> usize {
+    4
+}
+
+/// Bounded queue of moves entered while an animation is running.
+pub struct InputQueue {
+    moves: VecDeque<Direction>,
+    capacity: usize,
+}
+
+impl InputQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            moves: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add a move to the queue. Returns false if the queue is full and the
+    /// move was dropped.
+    pub fn push(&mut self, dir: Direction) -> bool {
+        if self.moves.len() >= self.capacity {
+            return false;
+        }
+        self.moves.push_back(dir);
+        true
+    }
+
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.moves.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut queue = InputQueue::new(2);
+        assert!(queue.push(Direction::N));
+        assert!(queue.push(Direction::E));
+        assert!(!queue.push(Direction::S));
+        assert_eq!(Some(Direction::N), queue.pop());
+        assert_eq!(Some(Direction::E), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+
+    #[test]
+    fn disabled() {
+        let mut queue = InputQueue::new(0);
+        assert!(!queue.push(Direction::W));
+        assert_eq!(None, queue.pop());
+    }
+}
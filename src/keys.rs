@@ -0,0 +1,315 @@
+use crate::game::Direction;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Keys without a character, as named in the configuration file.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NamedKey {
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// A function key, from 1 to 12.
+    F(u8),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    Named(NamedKey),
+    Character(char),
+}
+
+impl Key {
+    #[cfg(feature = "gl")]
+    pub fn from_event(event: &winit::event::KeyEvent) -> Vec<Self> {
+        use winit::keyboard::NamedKey as N;
+
+        let mut result = Vec::new();
+        match &event.text {
+            None => {
+                if let winit::keyboard::Key::Named(n) = event.logical_key {
+                    let named = match n {
+                        N::ArrowLeft => NamedKey::ArrowLeft,
+                        N::ArrowRight => NamedKey::ArrowRight,
+                        N::ArrowUp => NamedKey::ArrowUp,
+                        N::ArrowDown => NamedKey::ArrowDown,
+                        N::Home => NamedKey::Home,
+                        N::End => NamedKey::End,
+                        N::PageUp => NamedKey::PageUp,
+                        N::PageDown => NamedKey::PageDown,
+                        N::Insert => NamedKey::Insert,
+                        N::Delete => NamedKey::Delete,
+                        N::F1 => NamedKey::F(1),
+                        N::F2 => NamedKey::F(2),
+                        N::F3 => NamedKey::F(3),
+                        N::F4 => NamedKey::F(4),
+                        N::F5 => NamedKey::F(5),
+                        N::F6 => NamedKey::F(6),
+                        N::F7 => NamedKey::F(7),
+                        N::F8 => NamedKey::F(8),
+                        N::F9 => NamedKey::F(9),
+                        N::F10 => NamedKey::F(10),
+                        N::F11 => NamedKey::F(11),
+                        N::F12 => NamedKey::F(12),
+                        _ => return result,
+                    };
+                    result.push(Self::Named(named));
+                }
+            }
+            Some(t) => {
+                result.extend(t.chars().map(Key::Character));
+            }
+        }
+        result
+    }
+
+    /// The key of a terminal key event, if it can be bound.
+    #[cfg(feature = "tui")]
+    pub fn from_terminal(code: crossterm::event::KeyCode) -> Option<Self> {
+        use crossterm::event::KeyCode;
+
+        let key = match code {
+            KeyCode::Char(c) => Key::Character(c),
+            KeyCode::Enter => Key::Character('\r'),
+            KeyCode::Esc => Key::Character('\u{1b}'),
+            KeyCode::Tab => Key::Character('\t'),
+            KeyCode::Backspace => Key::Character('\u{8}'),
+            KeyCode::Left => Key::Named(NamedKey::ArrowLeft),
+            KeyCode::Right => Key::Named(NamedKey::ArrowRight),
+            KeyCode::Up => Key::Named(NamedKey::ArrowUp),
+            KeyCode::Down => Key::Named(NamedKey::ArrowDown),
+            KeyCode::Home => Key::Named(NamedKey::Home),
+            KeyCode::End => Key::Named(NamedKey::End),
+            KeyCode::PageUp => Key::Named(NamedKey::PageUp),
+            KeyCode::PageDown => Key::Named(NamedKey::PageDown),
+            KeyCode::Insert => Key::Named(NamedKey::Insert),
+            KeyCode::Delete => Key::Named(NamedKey::Delete),
+            KeyCode::F(n @ 1..=12) => Key::Named(NamedKey::F(n)),
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// Parse a key name, as used in the configuration file.
+    pub fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(Key::Character(c));
+        }
+
+        let key = match name.to_lowercase().as_str() {
+            "space" => Key::Character(' '),
+            "enter" | "return" => Key::Character('\r'),
+            "escape" | "esc" => Key::Character('\u{1b}'),
+            "tab" => Key::Character('\t'),
+            "backspace" => Key::Character('\u{8}'),
+            "left" | "arrowleft" => Key::Named(NamedKey::ArrowLeft),
+            "right" | "arrowright" => Key::Named(NamedKey::ArrowRight),
+            "up" | "arrowup" => Key::Named(NamedKey::ArrowUp),
+            "down" | "arrowdown" => Key::Named(NamedKey::ArrowDown),
+            "home" => Key::Named(NamedKey::Home),
+            "end" => Key::Named(NamedKey::End),
+            "pageup" => Key::Named(NamedKey::PageUp),
+            "pagedown" => Key::Named(NamedKey::PageDown),
+            "insert" => Key::Named(NamedKey::Insert),
+            "delete" => Key::Named(NamedKey::Delete),
+            "f1" => Key::Named(NamedKey::F(1)),
+            "f2" => Key::Named(NamedKey::F(2)),
+            "f3" => Key::Named(NamedKey::F(3)),
+            "f4" => Key::Named(NamedKey::F(4)),
+            "f5" => Key::Named(NamedKey::F(5)),
+            "f6" => Key::Named(NamedKey::F(6)),
+            "f7" => Key::Named(NamedKey::F(7)),
+            "f8" => Key::Named(NamedKey::F(8)),
+            "f9" => Key::Named(NamedKey::F(9)),
+            "f10" => Key::Named(NamedKey::F(10)),
+            "f11" => Key::Named(NamedKey::F(11)),
+            "f12" => Key::Named(NamedKey::F(12)),
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+/// Something the player can do by pressing a key.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Quit,
+    /// Pause the game and show the menu.
+    Pause,
+    /// Start a new game, after confirmation if the current one is not over.
+    NewGame,
+    Undo,
+    Hint,
+    Save,
+    Load,
+    /// Save the window contents to the pictures directory.
+    Screenshot,
+}
+
+impl Action {
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::Left => Some(Direction::W),
+            Action::Right => Some(Direction::E),
+            Action::Up => Some(Direction::N),
+            Action::Down => Some(Direction::S),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyNames {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Mapping from keys to actions, read from the `[keys]` table of the
+/// configuration file. Actions not mentioned in the table keep their default
+/// bindings.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "HashMap<Action, KeyNames>")]
+pub struct KeyBindings {
+    bindings: HashMap<Key, Action>,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Left, &["Left", "h"]),
+    (Action::Down, &["Down", "j"]),
+    (Action::Up, &["Up", "k"]),
+    (Action::Right, &["Right", "l"]),
+    (Action::Quit, &["q"]),
+    (Action::Pause, &["Escape", "p"]),
+    (Action::NewGame, &["n", "Space", "Enter"]),
+    (Action::Undo, &["u", "Backspace"]),
+    (Action::Hint, &["?"]),
+    (Action::Save, &["s"]),
+    (Action::Load, &["o"]),
+    (Action::Screenshot, &["F12"]),
+];
+
+impl KeyBindings {
+    fn new(actions: HashMap<Action, Vec<String>>) -> Result<Self, String> {
+        let mut bindings = HashMap::new();
+        let mut actions = actions.into_iter().collect::<Vec<_>>();
+        actions.sort_by_key(|(action, _)| format!("{:?}", action));
+
+        for (action, names) in actions {
+            for name in names {
+                let key = Key::parse(&name).ok_or_else(|| format!("unknown key `{}`", name))?;
+                if let Some(other) = bindings.insert(key, action) {
+                    if other != action {
+                        return Err(format!(
+                            "key `{}` is bound to both {:?} and {:?}",
+                            name, other, action
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+}
+
+fn default_actions() -> HashMap<Action, Vec<String>> {
+    DEFAULT_BINDINGS
+        .iter()
+        .map(|(action, names)| (*action, names.iter().map(|s| s.to_string()).collect()))
+        .collect()
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new(default_actions()).unwrap()
+    }
+}
+
+impl TryFrom<HashMap<Action, KeyNames>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(table: HashMap<Action, KeyNames>) -> Result<Self, String> {
+        let mut actions = default_actions();
+        for (action, names) in table {
+            let names = match names {
+                KeyNames::One(name) => vec![name],
+                KeyNames::Many(names) => names,
+            };
+            actions.insert(action, names);
+        }
+        Self::new(actions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(Some(Key::Character('x')), Key::parse("x"));
+        assert_eq!(Some(Key::Character(' ')), Key::parse("Space"));
+        assert_eq!(Some(Key::Named(NamedKey::ArrowUp)), Key::parse("up"));
+        assert_eq!(Some(Key::Named(NamedKey::F(5))), Key::parse("F5"));
+        assert_eq!(None, Key::parse("Hyper"));
+    }
+
+    #[test]
+    fn defaults() {
+        let keys = KeyBindings::default();
+        assert_eq!(Some(Action::Left), keys.action(Key::Character('h')));
+        assert_eq!(
+            Some(Action::Down),
+            keys.action(Key::Named(NamedKey::ArrowDown))
+        );
+        assert_eq!(Some(Action::Pause), keys.action(Key::Character('\u{1b}')));
+        assert_eq!(None, keys.action(Key::Character('x')));
+    }
+
+    #[test]
+    fn rebind() {
+        let keys: KeyBindings = toml::from_str(
+            r#"
+            left = ["a", "Left"]
+            hint = "h"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(Some(Action::Left), keys.action(Key::Character('a')));
+        assert_eq!(Some(Action::Hint), keys.action(Key::Character('h')));
+        assert_eq!(None, keys.action(Key::Character('?')));
+    }
+
+    #[test]
+    fn conflict() {
+        let err = toml::from_str::<KeyBindings>(r#"undo = "q""#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("key `q` is bound to both"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_key() {
+        let err = toml::from_str::<KeyBindings>(r#"save = "Hyper""#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown key `Hyper`"), "{}", err);
+    }
+}
@@ -1,3 +1,6 @@
+/// Fraction of the window taken by the HUD panel.
+const PANEL_FRACTION: f32 = 0.25;
+
 pub struct Layout {
     /// Size of unit square, including margin.
     pub unit: u32,
@@ -7,26 +10,45 @@ pub struct Layout {
     pub origin: (u32, u32),
     /// Size of the whole viewport, in pixels
     pub size: (u32, u32),
-    /// Number of tiles on each row.
-    pub width: usize,
+    /// Rect of the HUD panel, as `[x, y, width, height]`.
+    pub panel: [u32; 4],
+    /// Whether the panel is beside the board, rather than above it.
+    pub side_panel: bool,
 }
 
 impl Layout {
     pub fn compute(pixel_width: u32, pixel_height: u32, width: usize, height: usize) -> Self {
-        let unit = std::cmp::min(pixel_width / width as u32, pixel_height / height as u32);
+        // put the panel along the side with the most free space
+        let side_panel = pixel_width as u64 * height as u64 > pixel_height as u64 * width as u64;
+        let (panel, area) = if side_panel {
+            let w = (pixel_width as f32 * PANEL_FRACTION) as u32;
+            (
+                [pixel_width - w, 0, w, pixel_height],
+                (pixel_width - w, pixel_height),
+            )
+        } else {
+            let h = (pixel_height as f32 * PANEL_FRACTION) as u32;
+            (
+                [0, pixel_height - h, pixel_width, h],
+                (pixel_width, pixel_height - h),
+            )
+        };
+
+        let unit = std::cmp::min(area.0 / width as u32, area.1 / height as u32);
         let gap = (unit as f32 * 0.07) as u32;
         let display_width = width as u32 * unit;
         let display_height = height as u32 * unit;
 
-        let x0 = (pixel_width - display_width) / 2;
-        let y0 = (pixel_height - display_height) / 2;
+        let x0 = (area.0 - display_width) / 2;
+        let y0 = (area.1 - display_height) / 2;
 
         Self {
             unit,
             gap,
             origin: (x0, y0),
             size: (display_width, display_height),
-            width,
+            panel,
+            side_panel,
         }
     }
 
@@ -38,4 +60,148 @@ impl Layout {
             self.unit - 2 * self.gap,
         ]
     }
+
+    /// Rect of a tile at a fractional board position.
+    pub fn rect_at(&self, pos: (f32, f32)) -> [u32; 4] {
+        let size = self.unit - 2 * self.gap;
+        let offset = self.gap as f32;
+        [
+            (self.origin.0 as f32 + pos.0 * self.unit as f32 + offset).max(0.0) as u32,
+            (self.origin.1 as f32 + pos.1 * self.unit as f32 + offset).max(0.0) as u32,
+            size,
+            size,
+        ]
+    }
+
+    /// Top quarter of the board, where the title of a menu goes.
+    pub fn menu_title(&self) -> [u32; 4] {
+        let (w, h) = self.size;
+        [self.origin.0, self.origin.1 + h - h / 4, w, h / 4]
+    }
+
+    /// Rows for the entries of a menu shown over the board, below its title.
+    pub fn menu_rows(&self, n: usize) -> Vec<[u32; 4]> {
+        let (x, y) = self.origin;
+        let (w, h) = self.size;
+        let top = y + h - h / 4;
+        let row = (top - y)
+            .checked_div(n as u32)
+            .unwrap_or(0)
+            .min(self.unit * 2 / 3);
+        let inset = w / 10;
+        (0..n as u32)
+            .map(|i| {
+                [
+                    x + inset,
+                    top - (i + 1) * row + self.gap / 2,
+                    w - 2 * inset,
+                    row.saturating_sub(self.gap),
+                ]
+            })
+            .collect()
+    }
+
+    /// Split the panel into `n` boxes, from top to bottom for a side panel,
+    /// or from left to right for a header.
+    pub fn panel_cells(&self, n: usize) -> Vec<[u32; 4]> {
+        let [x, y, w, h] = self.panel;
+        let n = n as u32;
+        // at least a pixel between cells, unless the panel is too small
+        let gap = self.gap.max(1).min(w.min(h) / 2);
+        if self.side_panel {
+            let cell = h.saturating_sub(gap * (n + 1)).checked_div(n).unwrap_or(0);
+            (0..n)
+                .map(|i| {
+                    [
+                        x + gap,
+                        (y + h).saturating_sub((i + 1) * (cell + gap)),
+                        w.saturating_sub(2 * gap),
+                        cell,
+                    ]
+                })
+                .collect()
+        } else {
+            let cell = w.saturating_sub(gap * (n + 1)).checked_div(n).unwrap_or(0);
+            (0..n)
+                .map(|i| {
+                    [
+                        x + gap + i * (cell + gap),
+                        y + gap,
+                        cell,
+                        h.saturating_sub(2 * gap),
+                    ]
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_panel() {
+        let layout = Layout::compute(800, 600, 4, 4);
+        assert!(layout.side_panel);
+        assert_eq!([600, 0, 200, 600], layout.panel);
+        assert_eq!(150, layout.unit);
+        assert_eq!((0, 0), layout.origin);
+
+        let cells = layout.panel_cells(2);
+        assert_eq!([610, 305, 180, 285], cells[0]);
+        assert_eq!([610, 10, 180, 285], cells[1]);
+    }
+
+    #[test]
+    fn header() {
+        let layout = Layout::compute(600, 800, 4, 4);
+        assert!(!layout.side_panel);
+        assert_eq!([0, 600, 600, 200], layout.panel);
+        assert_eq!(150, layout.unit);
+
+        let cells = layout.panel_cells(3);
+        assert_eq!([10, 610, 186, 180], cells[0]);
+        assert_eq!([206, 610, 186, 180], cells[1]);
+    }
+
+    #[test]
+    fn menu() {
+        let layout = Layout::compute(800, 600, 4, 4);
+        assert_eq!([0, 450, 600, 150], layout.menu_title());
+
+        let rows = layout.menu_rows(3);
+        assert_eq!([60, 355, 480, 90], rows[0]);
+        assert_eq!([60, 255, 480, 90], rows[1]);
+        assert!(rows.iter().all(|r| r[1] + r[3] <= 450));
+    }
+
+    #[test]
+    fn board_never_overlaps_panel() {
+        // including windows resized to a few pixels
+        let sizes = [
+            (800, 600),
+            (600, 800),
+            (500, 500),
+            (1920, 400),
+            (300, 1000),
+            (10, 7),
+            (3, 1),
+            (0, 0),
+        ];
+        for (w, h) in sizes {
+            let layout = Layout::compute(w, h, 4, 4);
+            let [px, py, pw, ph] = layout.panel;
+            if layout.side_panel {
+                assert!(layout.origin.0 + layout.size.0 <= px);
+            } else {
+                assert!(layout.origin.1 + layout.size.1 <= py);
+            }
+            assert!(layout.panel_cells(0).is_empty());
+            for [x, y, cw, ch] in layout.panel_cells(5) {
+                assert!(x >= px && y >= py, "{}x{}", w, h);
+                assert!(x + cw <= px + pw && y + ch <= py + ph, "{}x{}", w, h);
+            }
+        }
+    }
 }
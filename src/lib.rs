@@ -0,0 +1,65 @@
+//! The game, its configuration and what is drawn for it, shared by the
+//! window frontend, drawn with OpenGL, and the terminal frontend.
+
+pub mod animation;
+#[cfg(feature = "gl")]
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod display_list;
+#[cfg(feature = "render")]
+pub mod font;
+pub mod game;
+pub mod gesture;
+#[cfg(feature = "gl")]
+pub mod glyphs;
+#[cfg(all(test, feature = "gl"))]
+mod golden;
+#[cfg(feature = "gl")]
+pub mod graphics;
+#[cfg(feature = "gl")]
+pub mod headless;
+pub mod hud;
+#[cfg(feature = "render")]
+pub mod image;
+pub mod input;
+pub mod keys;
+pub mod layout;
+#[cfg(feature = "render")]
+pub mod movie;
+pub mod profile;
+#[cfg(feature = "render")]
+pub mod raster;
+pub mod replay;
+pub mod save;
+#[cfg(feature = "gl")]
+pub mod scene;
+pub mod screenshot;
+pub mod session;
+pub mod settings;
+pub mod state;
+pub mod stats;
+#[cfg(feature = "render")]
+pub mod svg;
+pub mod text;
+pub mod theme;
+#[cfg(feature = "gl")]
+pub mod tiles;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod ui;
+
+#[cfg(feature = "gl")]
+use glutin::context::{ContextApi, Version};
+
+/// Size of the window when it opens, and default size of rendered images.
+pub const INITIAL_SIZE: (u32, u32) = (800, 600);
+
+/// Context versions to try, in order. Shaders are adapted to the version
+/// that was created, see `graphics::Glsl`.
+#[cfg(feature = "gl")]
+pub const CONTEXT_APIS: [ContextApi; 3] = [
+    ContextApi::OpenGl(Some(Version::new(4, 3))),
+    ContextApi::OpenGl(Some(Version::new(3, 3))),
+    ContextApi::Gles(Some(Version::new(3, 0))),
+];
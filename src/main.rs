@@ -1,76 +1,486 @@
-mod animation;
-mod config;
-mod game;
-mod glyphs;
-mod graphics;
-mod layout;
-mod scene;
-mod tiles;
-
-use animation::Animation;
-use config::Config;
-use game::{Direction, Game, Move};
+use anyhow::{bail, Context};
 use glow::HasContext;
 use glutin::{
     config::{ConfigTemplateBuilder, GlConfig},
-    context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext},
+    context::{ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext},
     display::{GetGlDisplay, GlDisplay},
     prelude::GlSurface,
     surface::{Surface, SwapInterval, WindowSurface},
 };
 use glutin_winit::{DisplayBuilder, GlWindow};
-use layout::Layout;
-use scene::Scene;
-use std::{num::NonZeroU32, rc::Rc, time::Duration};
+use iron::animation::Progress;
+use iron::clock::{Clock, ManualClock, SystemClock};
+use iron::config::Config;
+use iron::display_list::{self, DisplayList, Frame, Overlay};
+use iron::game::Direction;
+use iron::gesture::{ScrollDetector, SwipeDetector, MOUSE};
+use iron::hud::Hud;
+use iron::image::Image;
+use iron::keys::{Action, Key};
+use iron::layout::Layout;
+use iron::profile::FrameCounter;
+use iron::raster::Rasterizer;
+use iron::scene::Scene;
+use iron::session::Session;
+use iron::settings::{Setting, Settings};
+use iron::state::{Control, Item, Prompt, State};
+use iron::theme::Theme;
+use iron::ui::{Event, Ui};
+use iron::{
+    cli, config, font, graphics, headless, hud, movie, replay, save, screenshot, stats, svg, ui,
+    CONTEXT_APIS,
+};
+use std::{
+    num::NonZeroU32,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, KeyEvent, WindowEvent},
-    event_loop::{ActiveEventLoop, EventLoop},
-    keyboard::NamedKey,
+    event::{ElementState, MouseButton, MouseScrollDelta, StartCause, TouchPhase, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     raw_window_handle::HasWindowHandle,
     window::{Window, WindowId},
 };
 
-const INITIAL_SIZE: (u32, u32) = (800, 600);
-
-enum Key {
-    Named(NamedKey),
-    Character(char),
-}
+/// Keys that activate the selected menu item.
+const CONFIRM: [Key; 2] = [Key::Character('\r'), Key::Character(' ')];
 
-impl Key {
-    pub fn from_event(event: &KeyEvent) -> Vec<Self> {
-        let mut result = Vec::new();
-        match &event.text {
-            None => match event.logical_key {
-                winit::keyboard::Key::Named(n) => {
-                    result.push(Self::Named(n));
-                }
-                _ => (),
-            },
-            Some(t) => {
-                result.extend(t.chars().map(Key::Character));
-            }
-        }
-        result
-    }
-}
+/// Key that leaves a menu.
+const ESCAPE: Key = Key::Character('\u{1b}');
 
 struct Display {
     gl: Rc<glow::Context>,
     gl_surface: Surface<WindowSurface>,
     gl_context: PossiblyCurrentContext,
     config: Config,
+    /// Clock stepped once per frame, when rendering at a fixed frame rate.
+    frame_clock: Option<(Rc<ManualClock>, NonZeroU32)>,
     layout: Layout,
     scene: Scene,
-    animation: Option<Animation<Vec<Move>>>,
-    game: Game,
+    session: Session,
+    swipes: SwipeDetector,
+    scroll: ScrollDetector,
+    cursor: (f64, f64),
+    state: State,
+    ui: Ui<Control>,
+    prompt: Option<Prompt>,
+    /// Reports frame times, with the `debug` feature.
+    frames: FrameCounter,
+    /// File to save the next frame to.
+    screenshot: Option<PathBuf>,
+    /// Exit once the screenshot is saved, with `--screenshot`.
+    exit_after_screenshot: bool,
     window: Window,
 }
 
+impl Display {
+    /// Report errors, e.g. from saving the statistics, on the console.
+    fn report(&self, result: anyhow::Result<()>) {
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
+        }
+    }
+
+    /// Report the outcome of moves, showing the game over screen when the
+    /// last one ended the game.
+    fn played(&mut self, result: anyhow::Result<()>) {
+        self.report(result);
+        if self.session.animation.is_some() {
+            self.window.request_redraw();
+        }
+        if self.state == State::Playing && self.session.latest().is_over() {
+            self.state = State::GameOver;
+        }
+    }
+
+    /// Handle a move entered by the player.
+    fn input(&mut self, dir: Direction) {
+        if self.state != State::Playing {
+            return;
+        }
+        let result = self.session.input(dir, &self.config);
+        self.played(result);
+    }
+
+    /// The state showing the board, depending on whether the game is over.
+    fn board_state(&self) -> State {
+        if self.session.latest().is_over() {
+            State::GameOver
+        } else {
+            State::Playing
+        }
+    }
+
+    fn relayout(&mut self) {
+        let size = self.window.inner_size();
+        let game = self.session.latest();
+        self.layout = Layout::compute(size.width, size.height, game.width(), game.height());
+    }
+
+    /// Start a new game, with the board size from the configuration.
+    fn new_game(&mut self) {
+        let result = self.session.new_game(&self.config);
+        self.report(result);
+        self.set_state(State::Playing);
+        self.relayout();
+    }
+
+    fn undo(&mut self) {
+        if self.session.undo() {
+            self.state = State::Playing;
+        }
+    }
+
+    fn load(&mut self) -> anyhow::Result<()> {
+        self.session.load()?;
+        self.state = self.board_state();
+        self.relayout();
+        Ok(())
+    }
+
+    fn action(&mut self, action: Action, event_loop: &ActiveEventLoop) {
+        if let Some(dir) = action.direction() {
+            self.input(dir);
+            return;
+        }
+
+        match action {
+            Action::Quit => self.exit(event_loop),
+            Action::Pause => self.pause(),
+            Action::NewGame => {
+                if self.session.latest().is_over() {
+                    self.new_game();
+                } else {
+                    self.prompt = Some(Prompt::NewGame);
+                }
+            }
+            Action::Undo => self.undo(),
+            Action::Hint => self.prompt = self.session.latest().hint().map(Prompt::Hint),
+            Action::Save => {
+                self.prompt = Some(Prompt::Message(match self.session.save() {
+                    Ok(()) => "Game saved".to_string(),
+                    Err(e) => {
+                        eprintln!("Could not save game: {:#}", e);
+                        "Save failed".to_string()
+                    }
+                }))
+            }
+            Action::Load => {
+                if let Err(e) = self.load() {
+                    eprintln!("Could not load game: {:#}", e);
+                    self.prompt = Some(Prompt::Message("Load failed".to_string()));
+                }
+            }
+            Action::Screenshot => self.request_screenshot(),
+            Action::Left | Action::Right | Action::Up | Action::Down => {}
+        }
+    }
+
+    /// Save the next frame to a new file in the pictures directory.
+    fn request_screenshot(&mut self) {
+        match screenshot::default_path(SystemTime::now()) {
+            Ok(path) => self.screenshot = Some(path),
+            Err(e) => {
+                eprintln!("Could not take a screenshot: {:#}", e);
+                self.prompt = Some(Prompt::Message("Screenshot failed".to_string()));
+            }
+        }
+    }
+
+    /// Size of the framebuffer in physical pixels, which is larger than the
+    /// logical size of the window on HiDPI displays.
+    fn framebuffer_size(&self) -> (u32, u32) {
+        match (self.gl_surface.width(), self.gl_surface.height()) {
+            (Some(width), Some(height)) => (width, height),
+            _ => self.window.inner_size().into(),
+        }
+    }
+
+    /// Save the frame that was just drawn, before it is presented.
+    fn save_screenshot(&mut self, path: &Path, event_loop: &ActiveEventLoop) {
+        let (width, height) = self.framebuffer_size();
+        let pixels = graphics::read_pixels(&self.gl, width, height);
+        let saved = Image::from_gl(width, height, pixels).save_png(path);
+        if self.exit_after_screenshot {
+            if let Err(e) = saved {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+            event_loop.exit();
+            return;
+        }
+
+        self.prompt = Some(Prompt::Message(match saved {
+            Ok(()) => format!("Screenshot saved to {}", path.display()),
+            Err(e) => {
+                eprintln!("Could not save the screenshot: {:#}", e);
+                "Screenshot failed".to_string()
+            }
+        }));
+        self.window.request_redraw();
+    }
+
+    /// Handle a key press, depending on the current state.
+    fn key(&mut self, key: Key, event_loop: &ActiveEventLoop) {
+        let action = self.config.keys.action(key);
+        if action == Some(Action::Screenshot) {
+            // in menus too
+            self.request_screenshot();
+            return;
+        }
+        if self.state.on_board() {
+            if let Some(Prompt::NewGame) = self.prompt.take() {
+                if let Key::Character('y' | 'Y' | '\r' | ' ') = key {
+                    self.new_game();
+                }
+            } else if let Some(action) = action {
+                self.action(action, event_loop);
+            }
+            return;
+        }
+
+        // menus are driven by the keyboard focus
+        self.build_ui();
+        self.ui.ensure_focus();
+        let event = if CONFIRM.contains(&key) {
+            self.ui.activate()
+        } else if key == ESCAPE || action == Some(Action::Pause) {
+            self.back();
+            None
+        } else {
+            match action {
+                Some(Action::Up) => {
+                    self.ui.focus(-1);
+                    None
+                }
+                Some(Action::Down) => {
+                    self.ui.focus(1);
+                    None
+                }
+                Some(Action::Left) => self.ui.step(-1),
+                Some(Action::Right) => self.ui.step(1),
+                Some(Action::Quit) if self.state == State::Menu => {
+                    self.exit(event_loop);
+                    None
+                }
+                _ => None,
+            }
+        };
+        if let Some(event) = event {
+            self.ui_event(event, event_loop);
+        }
+    }
+
+    /// Refresh the widgets for the current state.
+    fn build_ui(&mut self) {
+        let settings = Settings::from_config(&self.config);
+        let widgets = self.state.widgets(
+            &self.layout,
+            &self.session.hud(),
+            &settings,
+            &self.session.stats,
+        );
+        self.ui.set(widgets);
+    }
+
+    /// Switch to another state, moving the keyboard focus to its first
+    /// widget.
+    fn set_state(&mut self, state: State) {
+        self.state = state;
+        self.ui.clear_focus();
+    }
+
+    fn ui_event(&mut self, event: Event<Control>, event_loop: &ActiveEventLoop) {
+        match event {
+            Event::Clicked(Control::Item(item)) => self.activate(item, event_loop),
+            Event::Clicked(Control::Back) => self.back(),
+            Event::Clicked(Control::NewGame) => self.action(Action::NewGame, event_loop),
+            Event::Clicked(Control::Undo) => self.undo(),
+            Event::Clicked(Control::Menu) => self.pause(),
+            Event::Changed(Control::Setting(setting), index) => self.set_setting(setting, index),
+            _ => {}
+        }
+    }
+
+    /// Leave the current menu.
+    fn back(&mut self) {
+        match self.state {
+            State::Paused => self.resume(),
+            State::Settings { .. } => self.close_settings(),
+            State::Stats { .. } => self.set_state(self.state.parent()),
+            State::Menu | State::Playing | State::GameOver => {}
+        }
+    }
+
+    /// Activate an item of the main or pause menu.
+    fn activate(&mut self, item: Item, event_loop: &ActiveEventLoop) {
+        let paused = self.state.paused();
+        match item {
+            Item::Play => self.resume(),
+            Item::NewGame => self.new_game(),
+            Item::Settings => self.set_state(State::Settings { paused }),
+            Item::Stats => self.set_state(State::Stats { paused }),
+            Item::MainMenu => self.set_state(State::Menu),
+            Item::Quit => self.exit(event_loop),
+        }
+    }
+
+    /// Leave the board for the pause menu, stopping the timer.
+    fn pause(&mut self) {
+        self.prompt = None;
+        self.session.timer.stop(Instant::now());
+        self.set_state(State::Paused);
+    }
+
+    /// Go back to the board from a menu.
+    fn resume(&mut self) {
+        self.set_state(self.board_state());
+        if self.state == State::Playing {
+            self.session.timer.resume(Instant::now());
+        }
+    }
+
+    /// Change a setting, and show the effect straight away where possible.
+    fn set_setting(&mut self, setting: Setting, index: usize) {
+        let mut settings = Settings::from_config(&self.config);
+        settings.select(setting, index);
+        settings.apply(&mut self.config);
+
+        match setting {
+            Setting::Theme => match Theme::load(&self.config.theme) {
+                Ok(theme) => self.scene.set_theme(theme),
+                Err(e) => eprintln!("{:#}", e),
+            },
+            Setting::TileRadius => self.scene.set_tile_radius(self.config.tile_radius),
+            Setting::Width | Setting::Height | Setting::Speed => {}
+        }
+    }
+
+    /// Leave the settings screen, starting a new game if the board size
+    /// changed, and write the settings back to the configuration file.
+    fn close_settings(&mut self) {
+        let parent = self.state.parent();
+        let size = (self.config.width, self.config.height);
+        let game = self.session.latest();
+        if size != (game.width(), game.height()) {
+            self.new_game();
+            if parent.paused() {
+                self.session.timer.stop(Instant::now());
+            }
+        }
+
+        if let Some(path) = config::default_path() {
+            if let Err(e) = Settings::from_config(&self.config).save(&path) {
+                eprintln!("Could not save settings: {:#}", e);
+            }
+        }
+        self.set_state(parent);
+    }
+
+    /// Screen to show over the board, if any.
+    fn overlay(&self) -> Option<Overlay> {
+        match self.state.title() {
+            Some(title) => Some(Overlay::menu(title)),
+            None if self.state == State::GameOver => Some(Overlay::message(
+                self.prompt
+                    .as_ref()
+                    .map_or("Game over".to_string(), Prompt::text),
+            )),
+            None => self.prompt.as_ref().map(|p| Overlay::message(p.text())),
+        }
+    }
+
+    /// Position of the cursor, with the origin at the bottom left.
+    fn cursor(&self) -> (f32, f32) {
+        let height = self.window.inner_size().height as f64;
+        (self.cursor.0 as f32, (height - self.cursor.1) as f32)
+    }
+
+    fn exit(&mut self, event_loop: &ActiveEventLoop) {
+        let result = self.session.record_score();
+        self.report(result);
+        event_loop.exit();
+    }
+
+    unsafe fn render(&mut self) {
+        let start = Instant::now();
+
+        self.build_ui();
+        let overlay = self.overlay();
+        let overlay = overlay.as_ref();
+
+        self.scene.clear();
+        if let Some(a) = &self.session.animation {
+            if a.is_finished() {
+                self.session.finish_animation();
+                self.scene.update(
+                    &self.layout,
+                    &self.session.game,
+                    None,
+                    Progress::DONE,
+                    overlay,
+                    self.ui.widgets(),
+                );
+            } else {
+                self.scene.update(
+                    &self.layout,
+                    &self.session.game,
+                    Some(&a.result),
+                    a.progress(),
+                    overlay,
+                    self.ui.widgets(),
+                );
+            }
+            self.window.request_redraw();
+        } else {
+            self.scene.update(
+                &self.layout,
+                &self.session.game,
+                None,
+                Progress::DONE,
+                overlay,
+                self.ui.widgets(),
+            );
+        }
+
+        if cfg!(feature = "debug") {
+            let now = Instant::now();
+            let uploaded = graphics::take_uploaded();
+            if let Some(report) = self.frames.frame(now, now - start, uploaded) {
+                println!(
+                    "{} frames, {} us/frame, {} bytes uploaded/frame",
+                    report.frames,
+                    report.frame_time.as_micros(),
+                    report.uploaded
+                );
+            }
+        }
+    }
+}
+
 impl ApplicationHandler for Display {
     fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
 
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            self.window.request_redraw();
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // wake up when the timer shown in the panel ticks
+        if self.session.timer.running() {
+            let elapsed = self.session.timer.elapsed(Instant::now());
+            let tick = Duration::from_secs(1) - Duration::from_nanos(elapsed.subsec_nanos() as u64);
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + tick));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -79,80 +489,108 @@ impl ApplicationHandler for Display {
     ) {
         match event {
             WindowEvent::CloseRequested => {
-                event_loop.exit();
+                self.exit(event_loop);
             }
             WindowEvent::RedrawRequested => unsafe {
-                render(
-                    &self.gl,
-                    &self.layout,
-                    &mut self.scene,
-                    &mut self.animation,
-                    &mut self.game,
-                    &mut self.window,
-                );
-                self.gl_surface.swap_buffers(&self.gl_context).unwrap();
+                self.render();
+                if let Some(path) = self.screenshot.take() {
+                    self.save_screenshot(&path, event_loop);
+                }
+                if let Err(e) = self.gl_surface.swap_buffers(&self.gl_context) {
+                    eprintln!("could not present the frame: {}", e);
+                }
+                if let Some((clock, fps)) = &self.frame_clock {
+                    clock.step_frame(*fps);
+                }
+                let result = self.session.tick(&self.config);
+                self.played(result);
             },
             WindowEvent::Resized(sz) => {
+                if let (Some(width), Some(height)) =
+                    (NonZeroU32::new(sz.width), NonZeroU32::new(sz.height))
+                {
+                    self.gl_surface.resize(&self.gl_context, width, height);
+                }
                 unsafe { self.gl.viewport(0, 0, sz.width as i32, sz.height as i32) };
 
-                self.layout =
-                    Layout::compute(sz.width, sz.height, self.game.width(), self.game.height());
+                self.relayout();
                 self.scene.resize(sz.width, sz.height);
             }
-            WindowEvent::KeyboardInput { ref event, .. } => {
-                if event.state == ElementState::Pressed {
-                    for key in Key::from_event(event) {
-                        if self.game.is_over() {
-                            match key {
-                                Key::Character(' ' | '\r' | 'n') => {
-                                    self.game = Game::new(self.game.width(), self.game.height());
-                                    self.game.add_random_tile();
-                                    self.animation = None;
-                                    self.window.request_redraw();
-                                }
-                                _ => {}
-                            };
-                        } else {
-                            let dir = match key {
-                                Key::Character('\u{1b}' | 'q') => {
-                                    event_loop.exit();
-                                    None
-                                }
-                                Key::Named(NamedKey::ArrowLeft) | Key::Character('h') => {
-                                    Some(Direction::W)
-                                }
-                                Key::Named(NamedKey::ArrowDown) | Key::Character('j') => {
-                                    Some(Direction::S)
-                                }
-                                Key::Named(NamedKey::ArrowUp) | Key::Character('k') => {
-                                    Some(Direction::N)
-                                }
-                                Key::Named(NamedKey::ArrowRight) | Key::Character('l') => {
-                                    Some(Direction::E)
-                                }
-                                _ => None,
-                            };
-                            // do not accept moves while another one is being animated
-                            if let Some(_) = self.animation {
-                                return;
-                            }
-                            if let Some(d) = dir {
-                                let mut game2 = self.game.clone();
-                                let moves = game2.step(d);
-                                if !moves.is_empty() {
-                                    game2.add_random_tile();
-                                }
-
-                                self.animation = Some(Animation::new(
-                                    Duration::from_millis(self.config.animation_duration_ms),
-                                    moves,
-                                    game2,
-                                ));
-                                self.window.request_redraw();
-                            }
+            WindowEvent::KeyboardInput { ref event, .. }
+                if event.state == ElementState::Pressed =>
+            {
+                for key in Key::from_event(event) {
+                    self.key(key, event_loop);
+                    self.window.request_redraw();
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x, position.y);
+                let hover = self.ui.hovered();
+                if let Some(event) = self.ui.cursor_moved(self.cursor()) {
+                    self.ui_event(event, event_loop);
+                    self.window.request_redraw();
+                }
+                if self.ui.hovered() != hover {
+                    self.window.request_redraw();
+                }
+                if !self.ui.capturing() {
+                    self.swipes.moved(MOUSE, self.cursor);
+                }
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    self.build_ui();
+                    if self.ui.hit(self.cursor()).is_some() {
+                        if let Some(event) = self.ui.press(self.cursor()) {
+                            self.ui_event(event, event_loop);
                         }
+                        self.window.request_redraw();
+                    } else {
+                        // input is timed in real time, even with a frame clock
+                        self.swipes.begin(MOUSE, self.cursor, Instant::now());
                     }
                 }
+                ElementState::Released => {
+                    if self.ui.capturing() {
+                        if let Some(event) = self.ui.release(self.cursor()) {
+                            self.ui_event(event, event_loop);
+                        }
+                        self.window.request_redraw();
+                    } else if let Some(d) = self.swipes.end(MOUSE, Instant::now()) {
+                        self.input(d);
+                    }
+                }
+            },
+            WindowEvent::Touch(touch) => {
+                let pos = (touch.location.x, touch.location.y);
+                match touch.phase {
+                    TouchPhase::Started => self.swipes.begin(touch.id, pos, Instant::now()),
+                    TouchPhase::Moved => self.swipes.moved(touch.id, pos),
+                    TouchPhase::Ended => {
+                        self.swipes.moved(touch.id, pos);
+                        if let Some(d) = self.swipes.end(touch.id, Instant::now()) {
+                            self.input(d);
+                        }
+                    }
+                    TouchPhase::Cancelled => self.swipes.cancel(touch.id),
+                }
+            }
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(delta),
+                phase,
+                ..
+            } if self.config.scroll_gestures => {
+                if phase == TouchPhase::Started {
+                    self.scroll.reset();
+                }
+                if let Some(d) = self.scroll.scroll(delta.x, delta.y) {
+                    self.input(d);
+                }
             }
             _ => (),
         }
@@ -160,124 +598,287 @@ impl ApplicationHandler for Display {
 }
 
 fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    let attrs = Window::default_attributes()
-        .with_title("Iron")
-        .with_transparent(false)
-        .with_inner_size(winit::dpi::LogicalSize::new(INITIAL_SIZE.0, INITIAL_SIZE.1));
-    let template = ConfigTemplateBuilder::new();
-    let display_builder = DisplayBuilder::new().with_window_attributes(Some(attrs));
-    let (window, gl_config) = display_builder
-        .build(&event_loop, template, |configs| {
-            configs
-                .reduce(|accum, config| {
-                    if config.num_samples() > accum.num_samples() {
-                        config
-                    } else {
-                        accum
-                    }
-                })
-                .unwrap()
-        })
-        .unwrap();
-    let window = window.unwrap();
-    let rwh = window.window_handle().unwrap();
-    let gl_display = gl_config.display();
-    let context_attributes = ContextAttributesBuilder::new()
-        .with_context_api(ContextApi::OpenGl(Some(glutin::context::Version {
-            major: 4,
-            minor: 1,
-        })))
-        .build(Some(rwh.into()));
-
-    let not_current_gl_context = unsafe {
-        gl_display
-            .create_context(&gl_config, &context_attributes)
-            .unwrap()
-    };
-    let attrs = window.build_surface_attributes(Default::default()).unwrap();
-    let gl_surface = unsafe {
-        gl_display
-            .create_window_surface(&gl_config, &attrs)
-            .unwrap()
-    };
+    let args = cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(2);
+    });
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
 
-    let gl_context = not_current_gl_context.make_current(&gl_surface).unwrap();
-    let gl = unsafe {
-        let ctx = glow::Context::from_loader_function_cstr(|s| gl_display.get_proc_address(s));
-        ctx.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        ctx.enable(glow::BLEND);
-        ctx
-    };
-    let gl = Rc::new(gl);
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
+    let theme = Theme::load(args.theme.as_ref().unwrap_or(&config.theme)).unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
 
-    gl_surface
-        .set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
-        .unwrap();
+    if let Some(path) = &args.replay {
+        let options = movie::Options {
+            size: args.size,
+            fps: args.fps,
+            speed: args.speed,
+            turns: args.turns.clone(),
+        };
+        if let Err(e) = render_replay(&config, &theme, path, &args.output, &options) {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(path) = &args.export {
+        if let Err(e) = export(&config, &theme, path, args.size) {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(path) = &args.thumbnail {
+        if let Err(e) = thumbnail(&config, theme, path, args.size) {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let config = get_config().unwrap_or(Config::default());
-    let mut game = Game::new(config.width, config.height);
-    game.add_random_tile();
+    let event_loop = EventLoop::new().unwrap_or_else(|e| {
+        eprintln!("could not connect to the display: {}", e);
+        std::process::exit(1);
+    });
+    let (window, gl_surface, gl_context, gl) = create_window(&event_loop, args.size)
+        .unwrap_or_else(|e| {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        });
 
-    let scene = Scene::new(gl.clone(), &config);
-    let layout = Layout::compute(INITIAL_SIZE.0, INITIAL_SIZE.1, game.width(), game.height());
-    let animation: Option<Animation<Vec<Move>>> = None;
+    let frame_clock = config
+        .frame_rate
+        .map(|fps| (Rc::new(ManualClock::new()), fps));
+    let clock: Rc<dyn Clock> = match &frame_clock {
+        Some((clock, _)) => clock.clone(),
+        None => Rc::new(SystemClock),
+    };
+
+    // the window is larger than the requested size on HiDPI displays
+    let size = window.inner_size();
+    let mut scene =
+        Scene::new(gl.clone(), &config, theme, load_font(&config)).unwrap_or_else(|e| {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        });
+    scene.resize(size.width, size.height);
+    let session = Session::new(&config, clock, stats::load_or_default());
+    let game = session.latest();
+    let layout = Layout::compute(size.width, size.height, game.width(), game.height());
+    let swipes = SwipeDetector::new(config.swipe_sensitivity);
+    let scroll = ScrollDetector::new(config.swipe_sensitivity);
 
     let mut display = Display {
         gl,
         gl_surface,
         gl_context,
         config,
+        frame_clock,
         layout,
-        game,
-        animation,
+        session,
+        swipes,
+        scroll,
+        cursor: (0.0, 0.0),
+        state: State::Menu,
+        ui: Ui::default(),
+        prompt: None,
+        frames: FrameCounter::new(Instant::now()),
+        screenshot: None,
+        exit_after_screenshot: false,
         scene,
         window,
     };
-    event_loop.run_app(&mut display).unwrap();
+    if let Some(path) = args.screenshot {
+        if let Err(e) = display.load() {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        display.screenshot = Some(path);
+        display.exit_after_screenshot = true;
+    }
+    if let Err(e) = event_loop.run_app(&mut display) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }
 
-unsafe fn render(
-    gl: &glow::Context,
-    layout: &Layout,
-    scene: &mut Scene,
-    anim: &mut Option<Animation<Vec<Move>>>,
-    game: &mut Game,
-    window: &mut Window,
-) {
-    use std::time::Instant;
-
-    let start = Instant::now();
-
-    gl.clear_color(0.148, 0.148, 0.148, 1.0);
-    gl.clear(glow::COLOR_BUFFER_BIT);
-    if let Some(a) = &anim {
-        let t = a.time().min(1.0);
-        if t >= 1.0 {
-            let a = anim.take().unwrap();
-            *game = a.result;
-            scene.update(&layout, &game, &Vec::new(), 1.0);
-        } else {
-            scene.update(&layout, &game, &a.inner, t);
-        }
-        window.request_redraw();
-    } else {
-        scene.update(&layout, &game, &Vec::new(), 1.0);
+fn load_font(config: &Config) -> rusttype::Font<'static> {
+    match &config.font {
+        Some(name) => font::load(name).unwrap_or_else(|e| {
+            eprintln!("{:#}, using the bundled font", e);
+            font::fallback()
+        }),
+        None => font::fallback(),
     }
+}
+
+/// Display list of the saved game, as shown during play.
+fn saved_game(
+    config: &Config,
+    theme: &Theme,
+    (width, height): (u32, u32),
+) -> anyhow::Result<DisplayList> {
+    let game = save::load(&save::default_path()?)?;
+    let mode = hud::mode(game.width(), game.height());
+    let stats = stats::load_or_default();
+    let hud = Hud {
+        score: game.score,
+        best: stats.best(&mode),
+        moves: game.moves,
+        time: Duration::ZERO,
+        mode,
+    };
+    let layout = Layout::compute(width, height, game.width(), game.height());
+    let widgets = State::Playing.widgets(&layout, &hud, &Settings::from_config(config), &stats);
+    Ok(display_list::build(&Frame {
+        layout: &layout,
+        theme,
+        radius: config.tile_radius,
+        game: &game,
+        target: None,
+        progress: Progress::DONE,
+        overlay: None,
+        widgets: widgets
+            .iter()
+            .map(|w| (w, ui::Status::Normal, false))
+            .collect(),
+    }))
+}
+
+/// Render the saved game as shown during play, without a window, and write
+/// it to a PNG file.
+fn thumbnail(config: &Config, theme: Theme, path: &Path, size: (u32, u32)) -> anyhow::Result<()> {
+    let list = saved_game(config, &theme, size)?;
+    let headless = headless::Headless::new(size.0, size.1)?;
+    let mut scene = Scene::new(headless.gl(), config, theme, load_font(config))?;
+    scene.resize(size.0, size.1);
+    let image = headless.render(|| {
+        scene.clear();
+        scene.draw(&list);
+    });
+    drop(scene);
+    image.save_png(path)
+}
 
-    if cfg!(feature = "debug") {
-        println!("{} us", (Instant::now() - start).as_micros());
+/// Render the saved game on the CPU, without any GL context, and write it to
+/// an SVG file if `path` ends with `.svg`, or to a PNG file otherwise.
+fn export(config: &Config, theme: &Theme, path: &Path, size: (u32, u32)) -> anyhow::Result<()> {
+    let list = saved_game(config, theme, size)?;
+    let font = load_font(config);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("svg") => {
+            svg::save(&list, size.0, size.1, &font, path)
+        }
+        _ => Rasterizer::new(font)
+            .render(&list, size.0, size.1)
+            .save_png(path),
     }
 }
 
-fn get_config() -> Option<Config> {
-    let mut path = dirs::config_dir()?;
-    path.push("iron");
-    path.push("config.toml");
+/// Render a replay file to a GIF, or to PNG files in a directory.
+fn render_replay(
+    config: &Config,
+    theme: &Theme,
+    path: &Path,
+    output: &Path,
+    options: &movie::Options,
+) -> anyhow::Result<()> {
+    let replay = replay::load(path)?;
+    let frames = movie::save(&replay, config, theme, load_font(config), options, output)?;
+    println!("{} frames written to {}", frames, output.display());
+    Ok(())
+}
 
-    let s = std::fs::read_to_string(path).ok()?;
+/// Open the window and create a GL context for it, trying the versions of
+/// `CONTEXT_APIS` in turn.
+fn create_window(
+    event_loop: &EventLoop<()>,
+    (width, height): (u32, u32),
+) -> anyhow::Result<(
+    Window,
+    Surface<WindowSurface>,
+    PossiblyCurrentContext,
+    Rc<glow::Context>,
+)> {
+    let attrs = Window::default_attributes()
+        .with_title("Iron")
+        .with_transparent(false)
+        .with_inner_size(winit::dpi::LogicalSize::new(width, height));
+    let template = ConfigTemplateBuilder::new();
+    let display_builder = DisplayBuilder::new().with_window_attributes(Some(attrs));
 
-    let config: Config = toml::from_str(&s).unwrap();
+    // The picker has to return a config, so when the display offers none it
+    // unwinds out of `build`, without running the panic hook, and the empty
+    // case is reported as an error below.
+    struct NoConfig;
+    let built = panic::catch_unwind(AssertUnwindSafe(|| {
+        display_builder.build(event_loop, template, |configs| {
+            configs
+                .reduce(|accum, config| {
+                    if config.num_samples() > accum.num_samples() {
+                        config
+                    } else {
+                        accum
+                    }
+                })
+                .unwrap_or_else(|| panic::resume_unwind(Box::new(NoConfig)))
+        })
+    }));
+    let (window, gl_config) = match built {
+        Ok(built) => built
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .context("could not find a suitable OpenGL configuration")?,
+        Err(payload) if payload.is::<NoConfig>() => bail!("no suitable GL config"),
+        Err(payload) => panic::resume_unwind(payload),
+    };
+    let window = window.context("could not create the window")?;
+    let rwh = window.window_handle()?;
+    let gl_display = gl_config.display();
+
+    let mut errors = Vec::new();
+    let not_current_gl_context = CONTEXT_APIS
+        .iter()
+        .find_map(|&api| {
+            let context_attributes = ContextAttributesBuilder::new()
+                .with_context_api(api)
+                .with_debug(cfg!(feature = "debug"))
+                .build(Some(rwh.into()));
+            unsafe { gl_display.create_context(&gl_config, &context_attributes) }
+                .map_err(|e| errors.push(format!("{:?}: {}", api, e)))
+                .ok()
+        })
+        .with_context(|| {
+            format!(
+                "could not create an OpenGL 3.3 or OpenGL ES 3.0 context\n{}",
+                errors.join("\n")
+            )
+        })?;
+
+    let attrs = window.build_surface_attributes(Default::default())?;
+    let gl_surface = unsafe { gl_display.create_window_surface(&gl_config, &attrs) }
+        .context("could not create the window surface")?;
+    let gl_context = not_current_gl_context
+        .make_current(&gl_surface)
+        .context("could not make the OpenGL context current")?;
+
+    let mut gl =
+        unsafe { glow::Context::from_loader_function_cstr(|s| gl_display.get_proc_address(s)) };
+    if cfg!(feature = "debug") && !graphics::enable_debug_output(&mut gl) {
+        eprintln!("KHR_debug is not supported, GL messages will not be shown");
+    }
+    if let Err(e) =
+        gl_surface.set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
+    {
+        eprintln!("could not enable vsync: {}", e);
+    }
 
-    Some(config)
+    Ok((window, gl_surface, gl_context, Rc::new(gl)))
 }
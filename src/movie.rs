@@ -0,0 +1,341 @@
+//! Replays rendered frame by frame with the software rasterizer, as an
+//! animated GIF or numbered PNG files. Animations are timed by a manual clock
+//! stepped once per frame, so that a replay always gives the same frames.
+
+use crate::animation::{Animation, Progress, Timeline};
+use crate::clock::ManualClock;
+use crate::config::Config;
+use crate::display_list::{self, DisplayList, Frame, Overlay};
+use crate::game::Game;
+use crate::hud::{self, Hud};
+use crate::image::Image;
+use crate::layout::Layout;
+use crate::raster::Rasterizer;
+use crate::replay::Replay;
+use crate::settings::Settings;
+use crate::state::State;
+use crate::stats::Stats;
+use crate::theme::Theme;
+use crate::ui::Status;
+use anyhow::{bail, Context};
+use rusttype::Font;
+use std::fs::File;
+use std::io::BufWriter;
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Time each board stays at rest before the next move.
+const PAUSE: Duration = Duration::from_millis(150);
+
+/// Time the last board stays, e.g. before a GIF starts again.
+const END_PAUSE: Duration = Duration::from_secs(2);
+
+/// Speeds a replay can be rendered at.
+pub const SPEEDS: RangeInclusive<f32> = 0.01..=100.0;
+
+/// GIF frame delays are in hundredths of a second.
+const MAX_GIF_FPS: u32 = 100;
+
+/// How to render a replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// Size of the images in pixels.
+    pub size: (u32, u32),
+    pub fps: u32,
+    /// How many times faster than played in the window.
+    pub speed: f32,
+    /// Turns to render, counted from 0.
+    pub turns: Range<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            size: crate::INITIAL_SIZE,
+            fps: 30,
+            speed: 1.0,
+            turns: 0..usize::MAX,
+        }
+    }
+}
+
+/// Draws the frames of a replay as the window shows them.
+struct Movie<'a> {
+    config: &'a Config,
+    theme: &'a Theme,
+    layout: Layout,
+    /// Best score shown in the panel, that of the last board rendered.
+    best: u64,
+}
+
+impl Movie<'_> {
+    fn frame(&self, game: &Game, animation: Option<&Animation>) -> DisplayList {
+        let (target, progress) = match animation {
+            Some(a) => (Some(&a.result), a.progress()),
+            None => (None, Progress::DONE),
+        };
+        let latest = target.unwrap_or(game);
+        let mode = hud::mode(latest.width(), latest.height());
+        let hud = Hud {
+            score: latest.score,
+            best: self.best,
+            moves: latest.moves,
+            time: Duration::ZERO,
+            mode,
+        };
+        let widgets = State::Playing.widgets(
+            &self.layout,
+            &hud,
+            &Settings::from_config(self.config),
+            &Stats::default(),
+        );
+        let overlay = latest.is_over().then(|| Overlay::message("Game over"));
+        display_list::build(&Frame {
+            layout: &self.layout,
+            theme: self.theme,
+            radius: self.config.tile_radius,
+            game,
+            target,
+            progress,
+            overlay: overlay.as_ref(),
+            widgets: widgets.iter().map(|w| (w, Status::Normal, false)).collect(),
+        })
+    }
+}
+
+/// Number of frames, `step` apart, lasting about `time`.
+fn frame_count(time: Duration, step: Duration) -> usize {
+    (time.as_secs_f64() / step.as_secs_f64()).round().max(1.0) as usize
+}
+
+/// Pass the display lists of the frames of a replay to `f`, in order.
+pub fn frames(
+    replay: &Replay,
+    config: &Config,
+    theme: &Theme,
+    options: &Options,
+    mut f: impl FnMut(&DisplayList) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    if options.fps == 0 {
+        bail!("the frame rate must be positive");
+    }
+    if !SPEEDS.contains(&options.speed) {
+        bail!(
+            "the speed must be between {} and {}",
+            SPEEDS.start(),
+            SPEEDS.end()
+        );
+    }
+    // time in the game between frames
+    let step = Duration::from_secs(1).mul_f32(options.speed) / options.fps;
+    if step.is_zero() {
+        bail!(
+            "the frame rate is too high for a speed of {}",
+            options.speed
+        );
+    }
+    let boards = replay.boards()?;
+    let last = options.turns.end.min(replay.turns.len());
+    let first = options.turns.start.min(last);
+    let boards = &boards[first..=last];
+
+    let (width, height) = options.size;
+    let movie = Movie {
+        config,
+        theme,
+        layout: Layout::compute(width, height, replay.start.width(), replay.start.height()),
+        best: boards[boards.len() - 1].score,
+    };
+    let clock = Rc::new(ManualClock::new());
+    let timeline = Timeline::from_config(config);
+
+    for _ in 0..frame_count(PAUSE, step) {
+        f(&movie.frame(&boards[0], None))?;
+    }
+    for turn in boards.windows(2) {
+        let animation = Animation::new(clock.clone(), timeline, turn[1].clone());
+        while !animation.is_finished() {
+            f(&movie.frame(&turn[0], Some(&animation)))?;
+            clock.advance(step);
+        }
+        for _ in 0..frame_count(PAUSE, step) {
+            f(&movie.frame(&turn[1], None))?;
+        }
+    }
+    for _ in 0..frame_count(END_PAUSE, step) {
+        f(&movie.frame(&boards[boards.len() - 1], None))?;
+    }
+    Ok(())
+}
+
+/// Render a replay to an animated GIF if `path` ends with `.gif`, and to
+/// numbered PNG files in the `path` directory otherwise. Returns the number
+/// of frames.
+pub fn save(
+    replay: &Replay,
+    config: &Config,
+    theme: &Theme,
+    font: Font<'static>,
+    options: &Options,
+    path: &Path,
+) -> anyhow::Result<usize> {
+    let gif = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+    let (width, height) = options.size;
+    let mut raster = Rasterizer::new(font);
+    let mut count = 0;
+    if !gif {
+        std::fs::create_dir_all(path)
+            .with_context(|| format!("could not create {}", path.display()))?;
+        frames(replay, config, theme, options, |list| {
+            count += 1;
+            let image = raster.render(list, width, height);
+            image.save_png(&path.join(format!("frame-{:05}.png", count)))
+        })?;
+        return Ok(count);
+    }
+
+    if options.fps > MAX_GIF_FPS {
+        bail!("GIFs have at most {} frames per second", MAX_GIF_FPS);
+    }
+    let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+        bail!("GIFs are at most {}x{} pixels", u16::MAX, u16::MAX);
+    };
+    let mut write = || -> anyhow::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, w, h, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        // time of a frame, in hundredths of a second
+        let time =
+            |frame: usize| (frame as u64 * 100 + options.fps as u64 / 2) / options.fps as u64;
+        // frames that do not change are shown longer instead of repeated
+        let mut shown: Option<(Image, usize)> = None;
+        let mut write_frame = |image: Image, start: usize, end: usize| -> anyhow::Result<()> {
+            let mut pixels = image.pixels;
+            let mut frame = gif::Frame::from_rgba_speed(w, h, &mut pixels, 10);
+            frame.delay = (time(end) - time(start)).min(u16::MAX as u64) as u16;
+            encoder.write_frame(&frame)?;
+            Ok(())
+        };
+        frames(replay, config, theme, options, |list| {
+            let image = raster.render(list, width, height);
+            if shown.as_ref().is_none_or(|(s, _)| s.pixels != image.pixels) {
+                if let Some((previous, start)) = shown.replace((image, count)) {
+                    write_frame(previous, start, count)?;
+                }
+            }
+            count += 1;
+            Ok(())
+        })?;
+        if let Some((last, start)) = shown {
+            write_frame(last, start, count)?;
+        }
+        Ok(())
+    };
+    write().with_context(|| format!("could not write {}", path.display()))?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut start = Game::new(2, 2);
+        start.tiles[0] = Some(1);
+        start.assign_ids();
+        let mut replay = Replay::new(&start);
+        replay.push(crate::game::Direction::E, (0, 1));
+        replay.push(crate::game::Direction::N, (0, 2));
+        replay
+    }
+
+    fn count(options: &Options) -> usize {
+        let config = Config::default();
+        let theme = Theme::load(&config.theme).unwrap();
+        let mut n = 0;
+        frames(&replay(), &config, &theme, options, |_| {
+            n += 1;
+            Ok(())
+        })
+        .unwrap();
+        n
+    }
+
+    #[test]
+    fn speed_and_trimming() {
+        let options = Options {
+            size: (40, 30),
+            ..Options::default()
+        };
+        let all = count(&options);
+        // pauses are sped up too
+        let fast = count(&Options {
+            speed: 2.0,
+            ..options.clone()
+        });
+        assert!(fast.abs_diff(all / 2) <= 2, "{} then {}", all, fast);
+
+        let first = count(&Options {
+            turns: 0..1,
+            ..options.clone()
+        });
+        let none = count(&Options {
+            turns: 5..9,
+            ..options.clone()
+        });
+        assert!(none < first && first < all);
+        // only the last board, at rest
+        let step = Duration::from_secs(1) / 30;
+        assert_eq!(
+            frame_count(PAUSE, step) + frame_count(END_PAUSE, step),
+            none
+        );
+    }
+
+    #[test]
+    fn invalid_speeds() {
+        let config = Config::default();
+        let theme = Theme::load(&config.theme).unwrap();
+        let invalid = [
+            (30, f32::INFINITY),
+            (30, 1e30),
+            (30, f32::NAN),
+            (30, 0.0),
+            (u32::MAX, 0.01),
+        ];
+        for (fps, speed) in invalid {
+            let options = Options {
+                size: (40, 30),
+                fps,
+                speed,
+                ..Options::default()
+            };
+            let frames = frames(&replay(), &config, &theme, &options, |_| Ok(()));
+            assert!(frames.is_err(), "{} fps at {}", fps, speed);
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        let config = Config::default();
+        let theme = Theme::load(&config.theme).unwrap();
+        let options = Options {
+            size: (40, 30),
+            ..Options::default()
+        };
+        let lists = || {
+            let mut lists = Vec::new();
+            frames(&replay(), &config, &theme, &options, |list| {
+                lists.push(list.clone());
+                Ok(())
+            })
+            .unwrap();
+            lists
+        };
+        assert_eq!(lists(), lists());
+    }
+}
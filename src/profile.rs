@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+/// How often the frame counter reports.
+const PERIOD: Duration = Duration::from_secs(1);
+
+/// Accumulates the time spent rendering frames, and the bytes uploaded to
+/// the GPU, to report averages about once per period.
+#[derive(Debug)]
+pub struct FrameCounter {
+    since: Instant,
+    frames: u32,
+    busy: Duration,
+    uploaded: usize,
+}
+
+/// Averages over a reporting period.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    pub frames: u32,
+    /// Average time spent rendering a frame.
+    pub frame_time: Duration,
+    /// Average number of bytes uploaded per frame.
+    pub uploaded: usize,
+}
+
+impl FrameCounter {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            since: now,
+            frames: 0,
+            busy: Duration::ZERO,
+            uploaded: 0,
+        }
+    }
+
+    /// Record a frame that took `time` to render and uploaded `uploaded`
+    /// bytes. Returns a report when a period has passed since the last one.
+    pub fn frame(&mut self, now: Instant, time: Duration, uploaded: usize) -> Option<Report> {
+        self.frames += 1;
+        self.busy += time;
+        self.uploaded += uploaded;
+        if now.duration_since(self.since) < PERIOD {
+            return None;
+        }
+
+        let report = Report {
+            frames: self.frames,
+            frame_time: self.busy / self.frames,
+            uploaded: self.uploaded / self.frames as usize,
+        };
+        *self = Self::new(now);
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let start = Instant::now();
+        let mut counter = FrameCounter::new(start);
+        let ms = Duration::from_millis;
+        assert_eq!(None, counter.frame(start + ms(500), ms(2), 100));
+        assert_eq!(
+            Some(Report {
+                frames: 2,
+                frame_time: ms(3),
+                uploaded: 50,
+            }),
+            counter.frame(start + ms(1000), ms(4), 0)
+        );
+        assert_eq!(None, counter.frame(start + ms(1500), ms(1), 0));
+    }
+}
@@ -0,0 +1,319 @@
+//! Software renderer, drawing display lists on the CPU into images that
+//! match what the GL backend draws: tiles are covered with the same signed
+//! distance as `tile.f.glsl`, and glyphs are rasterized at the same scale as
+//! in the glyph atlas, then sampled with bilinear filtering. Transformed
+//! tiles and glyphs are drawn by mapping pixel centres back into their rect.
+
+use crate::display_list::{self, by_depth, DisplayList, Text, Tile};
+use crate::image::Image;
+use crate::text::{layout, GLYPH_SCALE};
+use rusttype::{point, Font, Scale};
+use std::collections::HashMap;
+
+/// Coverage of a glyph, as stored in the atlas.
+struct Glyph {
+    width: u32,
+    height: u32,
+    coverage: Vec<f32>,
+    /// Left, top, right and bottom edges of the glyph, relative to the
+    /// bottom-left corner of its cell and to the size of the cell.
+    rect: [f32; 4],
+}
+
+impl Glyph {
+    fn new(font: &Font<'static>, c: char) -> Self {
+        let scale = Scale::uniform(GLYPH_SCALE);
+        let glyph = font.glyph(c).scaled(scale).positioned(point(0.0, 0.0));
+        let descent = font.v_metrics(scale).descent;
+        let Some(bb) = glyph.pixel_bounding_box() else {
+            return Glyph {
+                width: 0,
+                height: 0,
+                coverage: Vec::new(),
+                rect: [0.0; 4],
+            };
+        };
+
+        let (width, height) = (bb.width() as u32, bb.height() as u32);
+        let mut coverage = vec![0.0; (width * height) as usize];
+        glyph.draw(|x, y, v| {
+            // the atlas holds 8 bits per texel
+            coverage[(y * width + x) as usize] = (v * 255.0).round().clamp(0.0, 255.0) / 255.0;
+        });
+        Glyph {
+            width,
+            height,
+            coverage,
+            rect: [
+                bb.min.x as f32 / GLYPH_SCALE,
+                (-bb.min.y as f32 - descent) / GLYPH_SCALE,
+                bb.max.x as f32 / GLYPH_SCALE,
+                (-bb.max.y as f32 - descent) / GLYPH_SCALE,
+            ],
+        }
+    }
+
+    /// Bilinear sample at a position in texels, from the top left, with
+    /// nothing around the glyph.
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let texel = |x: i64, y: i64| {
+            if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                0.0
+            } else {
+                self.coverage[(y * self.width as i64 + x) as usize]
+            }
+        };
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Draws display lists into images, keeping rasterized glyphs across
+/// frames.
+pub struct Rasterizer {
+    font: Font<'static>,
+    glyphs: HashMap<char, Glyph>,
+}
+
+/// Pixels being drawn, with the origin at the bottom left.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Canvas {
+    /// Blend a colour over a pixel, rounding to 8 bits like the framebuffer.
+    fn blend(&mut self, x: u32, y: u32, colour: [f32; 3], alpha: f32) {
+        let pixel = &mut self.pixels[(y * self.width + x) as usize];
+        for (d, s) in pixel.iter_mut().zip(colour) {
+            *d = ((s * alpha + *d * (1.0 - alpha)) * 255.0).round() / 255.0;
+        }
+    }
+
+    /// Pixels whose centre is in the given rect, in pixels.
+    fn covered(&self, [x0, y0, x1, y1]: [f32; 4]) -> (u32, u32, u32, u32) {
+        let first = |v: f32| (v - 0.5).ceil().max(0.0) as u32;
+        (
+            first(x0),
+            first(y0),
+            first(x1).min(self.width),
+            first(y1).min(self.height),
+        )
+    }
+
+    fn fill(&mut self, tile: &Tile, radius: f32) {
+        let [x, y, w, h] = tile.rect.map(|v| v as f32);
+        let centre = display_list::centre(tile.rect);
+        let bounds = tile
+            .transform
+            .bounds(centre, display_list::edges(tile.rect));
+        let (px0, py0, px1, py1) = self.covered(bounds);
+        for py in py0..py1 {
+            for px in px0..px1 {
+                let p = (px as f32 + 0.5, py as f32 + 0.5);
+                let Some((tx, ty)) = tile.transform.invert(centre, p) else {
+                    continue;
+                };
+                // in the unit square of the tile
+                let (u, v) = ((tx - x) / w, (ty - y) / h);
+                if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                    continue;
+                }
+                let coverage = if radius > 0.0 {
+                    // as in tile.f.glsl
+                    let dx = ((u - 0.5).abs() - (0.5 - radius)).max(0.0);
+                    let dy = ((v - 0.5).abs() - (0.5 - radius)).max(0.0);
+                    smoothstep(-0.005, 0.005, radius - (dx * dx + dy * dy).sqrt())
+                } else {
+                    1.0
+                };
+                let alpha = tile.alpha * tile.transform.opacity * coverage;
+                self.blend(px, py, tile.colour, alpha);
+            }
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Rasterizer {
+    pub fn new(font: Font<'static>) -> Self {
+        Self {
+            font,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Draw a display list into an image of the given size.
+    pub fn render(&mut self, list: &DisplayList, width: u32, height: u32) -> Image {
+        let mut canvas = Canvas {
+            width,
+            height,
+            pixels: vec![list.background; (width * height) as usize],
+        };
+        for layer in &list.layers {
+            let radius = if layer.rounded { list.radius } else { 0.0 };
+            for tile in by_depth(&layer.tiles) {
+                canvas.fill(tile, radius);
+            }
+            for text in by_depth(&layer.texts) {
+                self.print(&mut canvas, text);
+            }
+        }
+
+        let pixels = canvas
+            .pixels
+            .chunks_exact(width as usize)
+            .rev()
+            .flatten()
+            .flat_map(|rgb| {
+                rgb.map(|c| (c * 255.0).round() as u8)
+                    .into_iter()
+                    .chain([255])
+            })
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn print(&mut self, canvas: &mut Canvas, text: &Text) {
+        let transform = &text.transform;
+        let centre = display_list::centre(text.rect);
+        for placed in layout(&self.font, &text.text, text.rect, &text.style) {
+            let font = &self.font;
+            let glyph = self
+                .glyphs
+                .entry(placed.c)
+                .or_insert_with(|| Glyph::new(font, placed.c));
+            if glyph.width == 0 {
+                continue;
+            }
+
+            let [cx, cy, size, _] = placed.cell().map(|v| v as f32);
+            let [left, top, right, bottom] = glyph.rect.map(|v| v * size);
+            let (x0, x1) = (cx + left, cx + right);
+            let (y0, y1) = (cy + bottom, cy + top);
+            let bounds = transform.bounds(centre, [x0, y0, x1, y1]);
+            let (px0, py0, px1, py1) = canvas.covered(bounds);
+            for py in py0..py1 {
+                for px in px0..px1 {
+                    let p = (px as f32 + 0.5, py as f32 + 0.5);
+                    let Some((tx, ty)) = transform.invert(centre, p) else {
+                        continue;
+                    };
+                    if tx < x0 || tx >= x1 || ty < y0 || ty >= y1 {
+                        continue;
+                    }
+                    let u = (tx - x0) / (x1 - x0) * glyph.width as f32;
+                    let v = (y1 - ty) / (y1 - y0) * glyph.height as f32;
+                    let alpha = glyph.sample(u, v) * transform.opacity;
+                    if alpha > 0.0 {
+                        canvas.blend(px, py, text.colour, alpha);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_list::{Layer, Transform};
+    use crate::text::TextStyle;
+
+    fn list(radius: f32, tiles: Vec<Tile>, texts: Vec<Text>) -> DisplayList {
+        DisplayList {
+            background: [0.0; 3],
+            radius,
+            layers: vec![Layer {
+                rounded: true,
+                tiles,
+                texts,
+            }],
+        }
+    }
+
+    #[test]
+    fn rounded_tiles() {
+        let tile = Tile {
+            colour: [1.0, 0.0, 0.0],
+            alpha: 1.0,
+            rect: [2, 2, 20, 20],
+            transform: Transform::IDENTITY,
+        };
+        let mut raster = Rasterizer::new(crate::font::fallback());
+        let image = raster.render(&list(0.25, vec![tile], Vec::new()), 24, 24);
+        let pixel = |x: usize, y: usize| &image.pixels[(y * 24 + x) * 4..][..4];
+
+        assert_eq!([255, 0, 0, 255], pixel(12, 12));
+        // outside the rect, and in a rounded corner
+        assert_eq!([0, 0, 0, 255], pixel(1, 12));
+        assert_eq!([0, 0, 0, 255], pixel(2, 2));
+        assert_eq!([255, 0, 0, 255], pixel(2, 12));
+    }
+
+    #[test]
+    fn text() {
+        let text = Text {
+            rect: [0, 0, 80, 80],
+            text: "8".to_string(),
+            colour: [1.0; 3],
+            style: TextStyle::TILE,
+            transform: Transform::IDENTITY,
+        };
+        let mut raster = Rasterizer::new(crate::font::fallback());
+        let image = raster.render(&list(0.0, Vec::new(), vec![text]), 80, 80);
+        let lit = image.pixels.chunks_exact(4).filter(|p| p[0] > 128).count();
+        assert!(lit > 50 && lit < 800, "{} pixels lit", lit);
+    }
+
+    #[test]
+    fn transformed_tiles() {
+        let tile = |colour, transform| Tile {
+            colour,
+            alpha: 1.0,
+            rect: [8, 8, 16, 16],
+            transform,
+        };
+        // a half-size tile turned by 45 degrees, under a faded one above it
+        let turned = tile(
+            [1.0, 0.0, 0.0],
+            Transform {
+                scale: 0.5,
+                rotation: std::f32::consts::FRAC_PI_4,
+                depth: 1.0,
+                ..Transform::IDENTITY
+            },
+        );
+        let faded = tile(
+            [0.0, 0.0, 1.0],
+            Transform {
+                opacity: 0.5,
+                ..Transform::IDENTITY
+            },
+        );
+        let mut raster = Rasterizer::new(crate::font::fallback());
+        let image = raster.render(&list(0.0, vec![turned, faded], Vec::new()), 32, 32);
+        let pixel = |x: usize, y: usize| &image.pixels[(y * 32 + x) * 4..][..4];
+
+        // drawn above, though listed first
+        assert_eq!([255, 0, 0, 255], pixel(16, 16));
+        // the corners of the turned tile are above and below its centre
+        assert_eq!([255, 0, 0, 255], pixel(16, 11));
+        assert_eq!([0, 0, 128, 255], pixel(11, 11));
+        assert_eq!([0, 0, 0, 255], pixel(4, 16));
+    }
+}
@@ -0,0 +1,216 @@
+//! Replays: the board a game started from, and every move with the tile that
+//! was added after it, so that the game can be played again exactly.
+
+use crate::game::{Direction, Game, Value};
+use crate::save::SavedGame;
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A move, and the tile added after it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Turn {
+    pub direction: Direction,
+    /// Index and value of the new tile.
+    pub spawn: (usize, Value),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub start: Game,
+    pub turns: Vec<Turn>,
+}
+
+impl Replay {
+    pub fn new(start: &Game) -> Self {
+        Self {
+            start: start.clone(),
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, direction: Direction, spawn: (usize, Value)) {
+        self.turns.push(Turn { direction, spawn });
+    }
+
+    /// Forget the last turn, when it is undone.
+    pub fn pop(&mut self) {
+        self.turns.pop();
+    }
+
+    /// The boards of the game, from the start board to the board after the
+    /// last turn, or an error if a turn cannot be played.
+    pub fn boards(&self) -> anyhow::Result<Vec<Game>> {
+        let mut boards = vec![self.start.clone()];
+        for (n, turn) in self.turns.iter().enumerate() {
+            let mut game = boards[n].clone();
+            if game.step(turn.direction).is_empty() {
+                bail!("move {} does not change the board", n + 1);
+            }
+            let (index, value) = turn.spawn;
+            if game.tiles.get(index) != Some(&None) {
+                bail!("the tile after move {} is not on an empty cell", n + 1);
+            }
+            game.add_tile(index, value);
+            boards.push(game);
+        }
+        Ok(boards)
+    }
+}
+
+/// On-disk representation of a replay.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedReplay {
+    /// One letter per move: l, r, u or d.
+    moves: String,
+    /// Tile added after each move: column and row, counted from the top left
+    /// like in the board, and number.
+    spawns: Vec<[u64; 3]>,
+    start: SavedGame,
+}
+
+fn letter(dir: Direction) -> char {
+    match dir {
+        Direction::W => 'l',
+        Direction::E => 'r',
+        Direction::N => 'u',
+        Direction::S => 'd',
+    }
+}
+
+impl From<&Replay> for SavedReplay {
+    fn from(replay: &Replay) -> Self {
+        let (width, height) = (replay.start.width(), replay.start.height());
+        SavedReplay {
+            moves: replay.turns.iter().map(|t| letter(t.direction)).collect(),
+            spawns: replay
+                .turns
+                .iter()
+                .map(|t| {
+                    let (index, value) = t.spawn;
+                    let (x, y) = (index % width, index / width);
+                    [x as u64, (height - y - 1) as u64, 1 << value]
+                })
+                .collect(),
+            start: SavedGame::from(&replay.start),
+        }
+    }
+}
+
+impl TryFrom<SavedReplay> for Replay {
+    type Error = anyhow::Error;
+
+    fn try_from(saved: SavedReplay) -> anyhow::Result<Replay> {
+        let start = Game::try_from(saved.start)?;
+        let (width, height) = (start.width(), start.height());
+        if saved.moves.chars().count() != saved.spawns.len() {
+            bail!(
+                "{} moves but {} new tiles",
+                saved.moves.chars().count(),
+                saved.spawns.len()
+            );
+        }
+
+        let mut turns = Vec::new();
+        for (n, (c, [x, y, number])) in saved.moves.chars().zip(saved.spawns).enumerate() {
+            let direction = match c {
+                'l' => Direction::W,
+                'r' => Direction::E,
+                'u' => Direction::N,
+                'd' => Direction::S,
+                c => bail!("invalid move {:?}", c),
+            };
+            let (x, y) = (x as usize, y as usize);
+            if x >= width || y >= height {
+                bail!("the tile after move {} is off the board", n + 1);
+            }
+            if !number.is_power_of_two() || number < 2 {
+                bail!("invalid tile {} after move {}", number, n + 1);
+            }
+            turns.push(Turn {
+                direction,
+                spawn: (x + (height - y - 1) * width, number.trailing_zeros() as u8),
+            });
+        }
+        Ok(Replay { start, turns })
+    }
+}
+
+/// Default location of the replay of the last finished or saved game.
+pub fn default_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().ok_or_else(|| anyhow!("no data directory"))?;
+    path.push("iron");
+    path.push("replay.toml");
+    Ok(path)
+}
+
+pub fn save(replay: &Replay, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let s = toml::to_string(&SavedReplay::from(replay))?;
+    std::fs::write(path, s).with_context(|| format!("could not write {}", path.display()))
+}
+
+/// Load a replay, checking that all its turns can be played.
+pub fn load(path: &Path) -> anyhow::Result<Replay> {
+    let load = || -> anyhow::Result<Replay> {
+        let s = std::fs::read_to_string(path)?;
+        let saved: SavedReplay = toml::from_str(&s)?;
+        let replay = Replay::try_from(saved)?;
+        replay.boards()?;
+        Ok(replay)
+    };
+    load().with_context(|| format!("could not read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut start = Game::new(3, 2);
+        start.tiles[0] = Some(1);
+        start.tiles[2] = Some(1);
+        start.assign_ids();
+        let mut replay = Replay::new(&start);
+        replay.push(Direction::W, (5, 2));
+        replay.push(Direction::N, (0, 1));
+        replay
+    }
+
+    #[test]
+    fn roundtrip() {
+        let replay = replay();
+        let saved = SavedReplay::from(&replay);
+        assert_eq!("lu", saved.moves);
+        assert_eq!(vec![[2, 0, 4], [0, 1, 2]], saved.spawns);
+
+        let s = toml::to_string(&saved).unwrap();
+        let loaded = Replay::try_from(toml::from_str::<SavedReplay>(&s).unwrap()).unwrap();
+        assert_eq!(replay, loaded);
+    }
+
+    #[test]
+    fn boards() {
+        let boards = replay().boards().unwrap();
+        assert_eq!(3, boards.len());
+        // the two tiles merged, then moved up
+        assert_eq!(
+            vec![Some(1), None, None, Some(2), None, Some(2)],
+            boards[2].tiles
+        );
+        assert_eq!((4, 2), (boards[2].score, boards[2].moves));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut replay = replay();
+        replay.push(Direction::N, (1, 1));
+        assert!(replay.boards().is_err());
+
+        let mut saved = SavedReplay::from(&self::replay());
+        saved.moves.pop();
+        assert!(Replay::try_from(saved).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::game::Game;
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// On-disk representation of a game. Each row lists tile numbers from left to
+/// right, top row first, with 0 for empty cells.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SavedGame {
+    pub board: Vec<Vec<u64>>,
+    #[serde(default)]
+    pub score: u64,
+    #[serde(default)]
+    pub moves: u32,
+}
+
+impl From<&Game> for SavedGame {
+    fn from(game: &Game) -> Self {
+        let board = (0..game.height())
+            .rev()
+            .map(|y| {
+                (0..game.width())
+                    .map(|x| match game.tiles[x + y * game.width()] {
+                        Some(v) => 1 << v,
+                        None => 0,
+                    })
+                    .collect()
+            })
+            .collect();
+        SavedGame {
+            board,
+            score: game.score,
+            moves: game.moves,
+        }
+    }
+}
+
+impl TryFrom<SavedGame> for Game {
+    type Error = anyhow::Error;
+
+    fn try_from(saved: SavedGame) -> anyhow::Result<Game> {
+        let height = saved.board.len();
+        let width = saved.board.first().map_or(0, |row| row.len());
+        if width == 0 || height == 0 {
+            bail!("empty board");
+        }
+
+        let mut game = Game::new(width, height);
+        for (row, tiles) in saved.board.iter().enumerate() {
+            if tiles.len() != width {
+                bail!(
+                    "row {} has {} tiles, expected {}",
+                    row + 1,
+                    tiles.len(),
+                    width
+                );
+            }
+            let y = height - row - 1;
+            for (x, &number) in tiles.iter().enumerate() {
+                game.tiles[x + y * width] = match number {
+                    0 => None,
+                    n if n.is_power_of_two() && n > 1 => Some(n.trailing_zeros() as u8),
+                    n => bail!("invalid tile {} in row {}", n, row + 1),
+                };
+            }
+        }
+        game.assign_ids();
+        game.score = saved.score;
+        game.moves = saved.moves;
+        Ok(game)
+    }
+}
+
+/// Default location of the saved game.
+pub fn default_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().ok_or_else(|| anyhow!("no data directory"))?;
+    path.push("iron");
+    path.push("save.toml");
+    Ok(path)
+}
+
+pub fn save(game: &Game, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let s = toml::to_string(&SavedGame::from(game))?;
+    std::fs::write(path, s).with_context(|| format!("could not write {}", path.display()))
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Game> {
+    let s = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    let saved: SavedGame = toml::from_str(&s)?;
+    saved.try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut game = Game::new(3, 2);
+        game.tiles[0] = Some(1);
+        game.tiles[4] = Some(11);
+        game.score = 4096;
+        game.moves = 1000;
+
+        let saved = SavedGame::from(&game);
+        assert_eq!(vec![vec![0, 2048, 0], vec![2, 0, 0]], saved.board);
+
+        let s = toml::to_string(&saved).unwrap();
+        let saved: SavedGame = toml::from_str(&s).unwrap();
+        let loaded = Game::try_from(saved).unwrap();
+        assert_eq!(game, loaded);
+        assert_eq!((4096, 1000), (loaded.score, loaded.moves));
+        // tiles can be told apart, e.g. to be drawn
+        assert!(loaded.id(0).is_some() && loaded.id(4).is_some());
+    }
+
+    #[test]
+    fn loaded_tiles_have_ids() {
+        let path = std::env::temp_dir().join("iron-loaded-ids.toml");
+        std::fs::write(&path, "board = [[2, 0, 4], [0, 8, 2]]\nscore = 16").unwrap();
+        let game = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // without IDs, tiles are not drawn until the next move
+        let ids = (0..game.tiles.len())
+            .filter(|&i| game.tiles[i].is_some())
+            .map(|i| game.id(i).expect("tile without an ID"))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(4, ids.len());
+    }
+
+    #[test]
+    fn invalid() {
+        let ragged: SavedGame = toml::from_str("board = [[0, 2], [4]]").unwrap();
+        assert!(Game::try_from(ragged).is_err());
+
+        let odd: SavedGame = toml::from_str("board = [[0, 3]]").unwrap();
+        assert!(Game::try_from(odd).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::anyhow;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A free path for a screenshot taken at `now`, in the pictures directory,
+/// or in the home directory if there is none.
+pub fn default_path(now: SystemTime) -> anyhow::Result<PathBuf> {
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .ok_or_else(|| anyhow!("no pictures directory"))?;
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    Ok(free_path(&dir, &format!("iron-{}", timestamp(secs))))
+}
+
+/// `dir/name.png`, or `dir/name-2.png` and so on if it already exists.
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.png", name));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.png", name, n));
+        n += 1;
+    }
+    path
+}
+
+/// UTC date and time of a Unix time, as used in file names.
+fn timestamp(secs: u64) -> String {
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // days to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + yoe + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!("1970-01-01_00-00-00", timestamp(0));
+        assert_eq!("2000-02-29_12-34-56", timestamp(951827696));
+        assert_eq!("2026-10-19_05-53-12", timestamp(1792389192));
+    }
+}
@@ -0,0 +1,293 @@
+//! The game being played, with its history, animation and records, shared
+//! by the window and the terminal frontends. Frontends handle their own
+//! screens and prompts, and report the errors returned here.
+
+use crate::animation::{Animation, Timeline};
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::game::{Direction, Game};
+use crate::hud::{self, Hud, Timer};
+use crate::input::InputQueue;
+use crate::replay::{self, Replay};
+use crate::save;
+use crate::stats::{self, Stats};
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Maximum number of moves that can be undone.
+const HISTORY_SIZE: usize = 256;
+
+pub struct Session {
+    clock: Rc<dyn Clock>,
+    /// Board shown at rest, or at the start of the running animation.
+    pub game: Game,
+    pub animation: Option<Animation>,
+    queue: InputQueue,
+    history: VecDeque<Game>,
+    /// Moves of the current game, saved with it and when it ends.
+    pub replay: Replay,
+    /// Time spent on the current game, in real time even with a frame clock.
+    pub timer: Timer,
+    pub stats: Stats,
+    /// Whether the current game was counted in the statistics, so that
+    /// losing it again after an undo does not count it twice.
+    counted: bool,
+    /// Whether statistics and replays are written to their files.
+    persist: bool,
+}
+
+impl Session {
+    /// Start a game with the board size from the configuration.
+    pub fn new(config: &Config, clock: Rc<dyn Clock>, stats: Stats) -> Self {
+        let mut game = Game::new(config.width, config.height);
+        game.add_random_tile();
+        Session {
+            clock,
+            replay: Replay::new(&game),
+            game,
+            animation: None,
+            queue: InputQueue::new(config.input_queue_size),
+            history: VecDeque::new(),
+            timer: Timer::default(),
+            stats,
+            counted: false,
+            persist: true,
+        }
+    }
+
+    /// The board as it will be once the running animation is over.
+    pub fn latest(&self) -> &Game {
+        self.animation.as_ref().map_or(&self.game, |a| &a.result)
+    }
+
+    /// Perform a move on the current board and start animating it. When it
+    /// ends the game, the statistics and the replay are saved, and errors
+    /// doing so are returned: the move is played anyway.
+    pub fn play(&mut self, dir: Direction, config: &Config) -> anyhow::Result<()> {
+        let mut game2 = self.game.clone();
+        if game2.step(dir).is_empty() {
+            return Ok(());
+        }
+        let spawn = game2.add_random_tile();
+
+        if self.history.len() >= HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.game.clone());
+        self.replay.push(dir, spawn);
+
+        let now = Instant::now();
+        self.timer.start(now);
+        let over = game2.is_over();
+        if over {
+            self.timer.stop(now);
+            if !self.counted {
+                self.stats.games += 1;
+                self.counted = true;
+            }
+            self.stats
+                .record(&hud::mode(game2.width(), game2.height()), game2.score);
+        }
+        self.animation = Some(Animation::new(
+            self.clock.clone(),
+            Timeline::from_config(config),
+            game2,
+        ));
+
+        if over {
+            let stats = self.save_stats();
+            let replay = self.save_replay();
+            stats.and(replay)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Jump to the end of the running animation.
+    pub fn finish_animation(&mut self) {
+        if let Some(a) = self.animation.take() {
+            self.game = a.result;
+        }
+    }
+
+    /// Handle a move entered by the player.
+    pub fn input(&mut self, dir: Direction, config: &Config) -> anyhow::Result<()> {
+        if self.animation.is_none() {
+            self.play(dir, config)
+        } else if config.snap_animations {
+            self.finish_animation();
+            self.play(dir, config)
+        } else {
+            // moves entered while the queue is full are dropped
+            self.queue.push(dir);
+            Ok(())
+        }
+    }
+
+    /// Finish the running animation once its time is up, and start the next
+    /// queued move.
+    pub fn tick(&mut self, config: &Config) -> anyhow::Result<()> {
+        if self.animation.as_ref().is_some_and(Animation::is_finished) {
+            self.finish_animation();
+        }
+        let mut result = Ok(());
+        while self.animation.is_none() {
+            match self.queue.pop() {
+                Some(dir) => result = result.and(self.play(dir, config)),
+                None => break,
+            }
+        }
+        result
+    }
+
+    /// Take back the last move, and the running animation with it. Return
+    /// whether there was a move to undo.
+    pub fn undo(&mut self) -> bool {
+        // the board before the running animation is the top of the history
+        self.animation = None;
+        self.queue.clear();
+        match self.history.pop_back() {
+            Some(game) => {
+                self.game = game;
+                self.replay.pop();
+                self.timer.resume(Instant::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Start a new game, with the board size from the configuration.
+    pub fn new_game(&mut self, config: &Config) -> anyhow::Result<()> {
+        let mut game = Game::new(config.width, config.height);
+        game.add_random_tile();
+        self.replace(game)
+    }
+
+    /// Continue the saved game.
+    pub fn load(&mut self) -> anyhow::Result<()> {
+        let game = save::load(&save::default_path()?)?;
+        self.replace(game)
+    }
+
+    /// Save the current game, and its moves so far.
+    pub fn save(&self) -> anyhow::Result<()> {
+        save::save(self.latest(), &save::default_path()?)?;
+        replay::save(&self.replay, &replay::default_path()?)
+    }
+
+    /// Play `game` from now on, recording the score of the current one.
+    fn replace(&mut self, game: Game) -> anyhow::Result<()> {
+        let recorded = self.record_score();
+        self.game = game;
+        self.replay = Replay::new(&self.game);
+        self.animation = None;
+        self.queue.clear();
+        self.history.clear();
+        self.timer.reset();
+        self.counted = false;
+        recorded
+    }
+
+    /// Update the best score with that of the current game, and save it if
+    /// it was beaten.
+    pub fn record_score(&mut self) -> anyhow::Result<()> {
+        let game = self.latest();
+        let mode = hud::mode(game.width(), game.height());
+        if self.stats.record(&mode, game.score) {
+            self.save_stats()?;
+        }
+        Ok(())
+    }
+
+    fn save_stats(&self) -> anyhow::Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        stats::default_path()
+            .and_then(|path| stats::save(&self.stats, &path))
+            .context("could not save statistics")
+    }
+
+    fn save_replay(&self) -> anyhow::Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        replay::default_path()
+            .and_then(|path| replay::save(&self.replay, &path))
+            .context("could not save the replay")
+    }
+
+    pub fn hud(&self) -> Hud {
+        let game = self.latest();
+        let mode = hud::mode(game.width(), game.height());
+        Hud {
+            score: game.score,
+            best: self.stats.best(&mode),
+            moves: game.moves,
+            time: self.timer.elapsed(Instant::now()),
+            mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    /// A session on `game`, that does not touch the statistics files.
+    fn session(game: Game) -> Session {
+        let mut session = Session::new(
+            &Config::default(),
+            Rc::new(ManualClock::new()),
+            Stats::default(),
+        );
+        session.persist = false;
+        session.replay = Replay::new(&game);
+        session.game = game;
+        session
+    }
+
+    #[test]
+    fn undo() {
+        let mut game = Game::new(4, 4);
+        game.add_tile(0, 1);
+        let mut s = session(game.clone());
+        let config = Config::default();
+
+        s.play(Direction::E, &config).unwrap();
+        assert_eq!(1, s.latest().moves);
+        assert_eq!(1, s.replay.turns.len());
+        assert!(s.timer.running());
+
+        assert!(s.undo());
+        assert!(s.animation.is_none());
+        assert_eq!(game, s.game);
+        assert!(s.replay.turns.is_empty());
+        assert!(!s.undo());
+    }
+
+    #[test]
+    fn games_counted_once() {
+        // any new tile fills the board without a merge
+        let mut game = Game::new(2, 1);
+        game.add_tile(0, 3);
+        let mut s = session(game.clone());
+        let config = Config::default();
+
+        s.play(Direction::E, &config).unwrap();
+        assert!(s.latest().is_over());
+        assert_eq!(1, s.stats.games);
+
+        s.undo();
+        s.play(Direction::E, &config).unwrap();
+        assert_eq!(1, s.stats.games);
+
+        s.replace(game).unwrap();
+        s.play(Direction::E, &config).unwrap();
+        assert_eq!(2, s.stats.games);
+    }
+}
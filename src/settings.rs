@@ -0,0 +1,267 @@
+use crate::config::Config;
+use crate::theme::Theme;
+use anyhow::Context;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// A configuration option that can be edited from the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    Width,
+    Height,
+    Speed,
+    Theme,
+    TileRadius,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 5] = [
+        Setting::Width,
+        Setting::Height,
+        Setting::Speed,
+        Setting::Theme,
+        Setting::TileRadius,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Width => "Width",
+            Setting::Height => "Height",
+            Setting::Speed => "Animation speed",
+            Setting::Theme => "Theme",
+            Setting::TileRadius => "Tile radius",
+        }
+    }
+}
+
+const MIN_SIZE: usize = 2;
+const MAX_SIZE: usize = 8;
+
+/// Animation speeds to choose from.
+const SPEEDS: &[f32] = &[0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0];
+
+/// Largest tile radius, which turns tiles into circles.
+const MAX_RADIUS: f32 = 0.5;
+const RADIUS_STEP: f32 = 0.01;
+
+/// Values of the settings that can be edited at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub width: usize,
+    pub height: usize,
+    pub animation_speed: f32,
+    pub theme: String,
+    pub tile_radius: f32,
+}
+
+impl Settings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            animation_speed: config.animation_speed,
+            theme: config.theme.clone(),
+            tile_radius: config.tile_radius,
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        config.width = self.width;
+        config.height = self.height;
+        config.animation_speed = self.animation_speed;
+        config.theme = self.theme.clone();
+        config.tile_radius = self.tile_radius;
+    }
+
+    pub fn value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Width => self.width.to_string(),
+            Setting::Height => self.height.to_string(),
+            Setting::Speed => format!("{}x", self.animation_speed),
+            Setting::Theme => self.theme.clone(),
+            Setting::TileRadius => format!("{:.2}", self.tile_radius),
+        }
+    }
+
+    /// Names of the themes to choose from: the built-in ones, and the
+    /// current one if it is not built-in.
+    pub fn themes(&self) -> Vec<String> {
+        let mut themes = Theme::builtin_names()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if !themes.contains(&self.theme) {
+            themes.insert(0, self.theme.clone());
+        }
+        themes
+    }
+
+    /// Number of values a setting can take.
+    pub fn count(&self, setting: Setting) -> usize {
+        match setting {
+            Setting::Width | Setting::Height => MAX_SIZE - MIN_SIZE + 1,
+            Setting::Speed => SPEEDS.len(),
+            Setting::Theme => self.themes().len(),
+            Setting::TileRadius => (MAX_RADIUS / RADIUS_STEP).round() as usize + 1,
+        }
+    }
+
+    /// Position of the current value of a setting among its possible values.
+    pub fn index(&self, setting: Setting) -> usize {
+        let size = |n: usize| n.clamp(MIN_SIZE, MAX_SIZE) - MIN_SIZE;
+        match setting {
+            Setting::Width => size(self.width),
+            Setting::Height => size(self.height),
+            // the closest speed, in case it was set by hand
+            Setting::Speed => SPEEDS
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    let da = (*a - self.animation_speed).abs();
+                    let db = (*b - self.animation_speed).abs();
+                    da.total_cmp(&db)
+                })
+                .map_or(0, |(i, _)| i),
+            Setting::Theme => self
+                .themes()
+                .iter()
+                .position(|t| *t == self.theme)
+                .unwrap_or(0),
+            Setting::TileRadius => {
+                ((self.tile_radius / RADIUS_STEP).round() as usize).min(self.count(setting) - 1)
+            }
+        }
+    }
+
+    /// Set a setting to the value at `index` among its possible values.
+    pub fn select(&mut self, setting: Setting, index: usize) {
+        let index = index.min(self.count(setting) - 1);
+        match setting {
+            Setting::Width => self.width = MIN_SIZE + index,
+            Setting::Height => self.height = MIN_SIZE + index,
+            Setting::Speed => self.animation_speed = SPEEDS[index],
+            Setting::Theme => self.theme = self.themes().swap_remove(index),
+            Setting::TileRadius => self.tile_radius = index as f32 * RADIUS_STEP,
+        }
+    }
+
+    /// Write the settings into a parsed configuration file, keeping other
+    /// options as they are.
+    fn merge(&self, doc: &mut DocumentMut) {
+        set(doc, "width", self.width as i64);
+        set(doc, "height", self.height as i64);
+        set(doc, "animation_speed", self.animation_speed as f64);
+        set(doc, "theme", self.theme.as_str());
+        // round away the noise of the conversion to f64
+        let radius = (self.tile_radius as f64 * 1000.0).round() / 1000.0;
+        set(doc, "tile_radius", radius);
+    }
+
+    /// Save the settings to the configuration file at `path`, creating it if
+    /// needed.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut doc = match std::fs::read_to_string(path) {
+            Ok(s) => s
+                .parse::<DocumentMut>()
+                .with_context(|| format!("invalid configuration in {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+            Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+        };
+        self.merge(&mut doc);
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, doc.to_string())
+            .with_context(|| format!("could not write {}", path.display()))
+    }
+}
+
+/// Set a top-level key, keeping the comments around its previous value, or
+/// add it after the other keys.
+fn set(doc: &mut DocumentMut, key: &str, value: impl Into<Value>) {
+    let mut value = value.into();
+    if let Some(old) = doc.get(key).and_then(Item::as_value) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    doc[key] = Item::Value(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select() {
+        let mut settings = Settings::from_config(&Config::default());
+        assert_eq!(2, settings.index(Setting::Width));
+        settings.select(Setting::Width, 10);
+        assert_eq!(MAX_SIZE, settings.width);
+        settings.select(Setting::Height, 1);
+        assert_eq!(3, settings.height);
+
+        settings.animation_speed = 1.1;
+        assert_eq!(1.0, SPEEDS[settings.index(Setting::Speed)]);
+        settings.select(Setting::Speed, 4);
+        assert_eq!(1.5, settings.animation_speed);
+
+        assert_eq!(vec!["default", "classic", "mono"], settings.themes());
+        settings.select(Setting::Theme, 1);
+        assert_eq!("classic", settings.theme);
+
+        settings.theme = "custom.toml".to_string();
+        assert_eq!(0, settings.index(Setting::Theme));
+        assert_eq!(4, settings.count(Setting::Theme));
+        settings.select(Setting::Theme, 1);
+        assert_eq!("default", settings.theme);
+
+        settings.tile_radius = 0.03;
+        assert_eq!(3, settings.index(Setting::TileRadius));
+        assert_eq!(51, settings.count(Setting::TileRadius));
+        settings.select(Setting::TileRadius, 5);
+        assert!((settings.tile_radius - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn merge() {
+        let mut doc: DocumentMut = r#"
+            width = 4
+            easing = "cubic"
+
+            [keys]
+            undo = "z"
+            "#
+        .parse()
+        .unwrap();
+
+        let mut settings = Settings::from_config(&Config::default());
+        settings.width = 6;
+        settings.theme = "mono".to_string();
+        settings.tile_radius = 0.07;
+        settings.merge(&mut doc);
+
+        let config: Config = toml::from_str(&doc.to_string()).unwrap();
+        assert_eq!(Some("z"), doc["keys"]["undo"].as_str());
+        assert_eq!(settings, Settings::from_config(&config));
+    }
+
+    #[test]
+    fn merge_keeps_comments() {
+        let text = "# board\nwidth = 4 # columns\neasing = \"cubic\"\n\n# bindings\n[keys]\nundo = \"z\"\n";
+        let mut doc: DocumentMut = text.parse().unwrap();
+        let mut settings = Settings::from_config(&Config::default());
+        settings.width = 6;
+        settings.merge(&mut doc);
+
+        let saved = doc.to_string();
+        assert!(
+            saved.starts_with("# board\nwidth = 6 # columns\neasing = \"cubic\"\n"),
+            "{}",
+            saved
+        );
+        assert!(
+            saved.ends_with("# bindings\n[keys]\nundo = \"z\"\n"),
+            "{}",
+            saved
+        );
+    }
+}
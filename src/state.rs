@@ -0,0 +1,303 @@
+use crate::game::Direction;
+use crate::hud::Hud;
+use crate::layout::Layout;
+use crate::settings::{Setting, Settings};
+use crate::stats::Stats;
+use crate::ui::{Kind, Widget};
+
+/// What the app is showing, and how it reacts to input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    /// Main menu, shown at startup.
+    Menu,
+    Playing,
+    /// Pause menu.
+    Paused,
+    GameOver,
+    /// Settings screen. `paused` is whether it was opened from the pause
+    /// menu rather than the main menu.
+    Settings {
+        paused: bool,
+    },
+    Stats {
+        paused: bool,
+    },
+}
+
+/// An entry of the main or pause menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Item {
+    /// Go back to the board.
+    Play,
+    NewGame,
+    Settings,
+    Stats,
+    MainMenu,
+    Quit,
+}
+
+impl Item {
+    pub fn label(self) -> &'static str {
+        match self {
+            Item::Play => "Play",
+            Item::NewGame => "New game",
+            Item::Settings => "Settings",
+            Item::Stats => "Statistics",
+            Item::MainMenu => "Main menu",
+            Item::Quit => "Quit",
+        }
+    }
+}
+
+const MAIN_MENU: &[Item] = &[
+    Item::Play,
+    Item::NewGame,
+    Item::Settings,
+    Item::Stats,
+    Item::Quit,
+];
+
+const PAUSE_MENU: &[Item] = &[
+    Item::Play,
+    Item::NewGame,
+    Item::Settings,
+    Item::Stats,
+    Item::MainMenu,
+];
+
+/// Message shown over the board.
+pub enum Prompt {
+    /// Ask for confirmation before abandoning the current game.
+    NewGame,
+    Hint(Direction),
+    Message(String),
+}
+
+impl Prompt {
+    pub fn text(&self) -> String {
+        match self {
+            Prompt::NewGame => "New game? (y/n)".to_string(),
+            Prompt::Hint(dir) => format!(
+                "Hint: {}",
+                match dir {
+                    Direction::E => "right",
+                    Direction::N => "up",
+                    Direction::W => "left",
+                    Direction::S => "down",
+                }
+            ),
+            Prompt::Message(msg) => msg.clone(),
+        }
+    }
+}
+
+/// Identifies the widgets of the app.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Item(Item),
+    Setting(Setting),
+    /// Leave a settings or stats screen.
+    Back,
+    NewGame,
+    Undo,
+    /// Open the pause menu.
+    Menu,
+    /// A label or value.
+    Text,
+}
+
+impl State {
+    /// Items of the menu shown in this state.
+    pub fn items(self) -> &'static [Item] {
+        match self {
+            State::Menu => MAIN_MENU,
+            State::Paused => PAUSE_MENU,
+            _ => &[],
+        }
+    }
+
+    /// Whether the board is live, rather than covered by a menu.
+    pub fn on_board(self) -> bool {
+        matches!(self, State::Playing | State::GameOver)
+    }
+
+    /// The menu that opened a settings or stats screen.
+    pub fn parent(self) -> State {
+        match self {
+            State::Settings { paused: true } | State::Stats { paused: true } => State::Paused,
+            _ => State::Menu,
+        }
+    }
+
+    /// Whether the state was reached from the pause menu.
+    pub fn paused(self) -> bool {
+        match self {
+            State::Paused => true,
+            State::Settings { paused } | State::Stats { paused } => paused,
+            _ => false,
+        }
+    }
+
+    /// Title of the screen shown over the board, for states with a menu.
+    pub fn title(self) -> Option<&'static str> {
+        match self {
+            State::Menu => Some("Iron"),
+            State::Paused => Some("Paused"),
+            State::Settings { .. } => Some("Settings"),
+            State::Stats { .. } => Some("Statistics"),
+            State::Playing | State::GameOver => None,
+        }
+    }
+
+    /// Widgets of the HUD panel, and of the menu of this state if any.
+    pub fn widgets(
+        self,
+        layout: &Layout,
+        hud: &Hud,
+        settings: &Settings,
+        stats: &Stats,
+    ) -> Vec<Widget<Control>> {
+        let entries = hud.entries();
+        let buttons = [
+            (Control::NewGame, "New"),
+            (Control::Undo, "Undo"),
+            (Control::Menu, "Menu"),
+        ];
+        let cells = layout.panel_cells(entries.len() + buttons.len());
+        let (values, rest) = cells.split_at(entries.len());
+        let mut widgets = values
+            .iter()
+            .zip(entries)
+            .map(|(&rect, (label, value))| {
+                Widget::new(Control::Text, rect, label, Kind::Value(value))
+            })
+            .collect::<Vec<_>>();
+        if self.on_board() {
+            widgets.extend(
+                rest.iter()
+                    .zip(buttons)
+                    .map(|(&rect, (id, text))| Widget::new(id, rect, text, Kind::Button)),
+            );
+        }
+
+        match self {
+            State::Menu | State::Paused => {
+                let items = self.items();
+                let rows = layout.menu_rows(items.len());
+                widgets.extend(rows.into_iter().zip(items).map(|(rect, &item)| {
+                    Widget::new(Control::Item(item), rect, item.label(), Kind::Button)
+                }));
+            }
+            State::Settings { .. } => {
+                let rows = layout.menu_rows(Setting::ALL.len() + 1);
+                for (&rect, setting) in rows.iter().zip(Setting::ALL) {
+                    let kind = match setting {
+                        Setting::Theme => Kind::List {
+                            items: settings.themes(),
+                            selected: settings.index(setting),
+                        },
+                        _ => Kind::Slider {
+                            index: settings.index(setting),
+                            count: settings.count(setting),
+                        },
+                    };
+                    let text = match kind {
+                        Kind::Slider { .. } => {
+                            format!("{}: {}", setting.label(), settings.value(setting))
+                        }
+                        _ => setting.label().to_string(),
+                    };
+                    widgets.push(Widget::new(Control::Setting(setting), rect, text, kind));
+                }
+                let back = rows[rows.len() - 1];
+                widgets.push(Widget::new(Control::Back, back, "Back", Kind::Button));
+            }
+            State::Stats { .. } => {
+                let mut best = stats.best.iter().collect::<Vec<_>>();
+                best.sort();
+                let lines = std::iter::once(format!("Games: {}", stats.games))
+                    .chain(
+                        best.into_iter()
+                            .map(|(mode, score)| format!("Best {}: {}", mode, score)),
+                    )
+                    .collect::<Vec<_>>();
+                let rows = layout.menu_rows(lines.len() + 1);
+                widgets.extend(
+                    rows.iter()
+                        .zip(lines)
+                        .map(|(&rect, line)| Widget::new(Control::Text, rect, line, Kind::Label)),
+                );
+                let back = rows[rows.len() - 1];
+                widgets.push(Widget::new(Control::Back, back, "Back", Kind::Button));
+            }
+            State::Playing | State::GameOver => {}
+        }
+        widgets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::ui::{Event, Ui};
+    use std::time::Duration;
+
+    fn widgets(state: State) -> Vec<Widget<Control>> {
+        let layout = Layout::compute(800, 600, 4, 4);
+        let hud = Hud {
+            score: 0,
+            best: 0,
+            moves: 0,
+            time: Duration::ZERO,
+            mode: "4x4".to_string(),
+        };
+        let settings = Settings::from_config(&Config::default());
+        state.widgets(&layout, &hud, &settings, &Stats::default())
+    }
+
+    #[test]
+    fn parent() {
+        let settings = State::Settings { paused: true };
+        assert_eq!(State::Paused, settings.parent());
+        assert_eq!(State::Menu, State::Stats { paused: false }.parent());
+        assert!(settings.paused());
+        assert!(!State::Playing.paused());
+    }
+
+    #[test]
+    fn hud_buttons_only_on_board() {
+        let ids = |state| {
+            widgets(state)
+                .into_iter()
+                .map(|w| w.id)
+                .filter(|&id| id != Control::Text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![Control::NewGame, Control::Undo, Control::Menu],
+            ids(State::Playing)
+        );
+        assert!(!ids(State::Paused).contains(&Control::Undo));
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        let mut ui = Ui::default();
+        ui.set(widgets(State::Menu));
+        ui.ensure_focus();
+        ui.focus(1);
+        assert_eq!(
+            Some(Event::Clicked(Control::Item(Item::NewGame))),
+            ui.activate()
+        );
+
+        ui.set(widgets(State::Settings { paused: false }));
+        ui.clear_focus();
+        ui.ensure_focus();
+        assert_eq!(
+            Some(Event::Changed(Control::Setting(Setting::Width), 3)),
+            ui.step(1)
+        );
+    }
+}
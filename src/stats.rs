@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Records kept across games.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct Stats {
+    /// Best score for each mode.
+    #[serde(default)]
+    pub best: HashMap<String, u64>,
+    /// Number of games played until no move was left.
+    #[serde(default)]
+    pub games: u32,
+}
+
+impl Stats {
+    pub fn best(&self, mode: &str) -> u64 {
+        self.best.get(mode).copied().unwrap_or(0)
+    }
+
+    /// Update the best score of a mode. Return whether it was beaten.
+    pub fn record(&mut self, mode: &str, score: u64) -> bool {
+        if score <= self.best(mode) {
+            return false;
+        }
+        self.best.insert(mode.to_string(), score);
+        true
+    }
+}
+
+/// Default location of the statistics file.
+pub fn default_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().ok_or_else(|| anyhow!("no data directory"))?;
+    path.push("iron");
+    path.push("stats.toml");
+    Ok(path)
+}
+
+pub fn save(stats: &Stats, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let s = toml::to_string(stats)?;
+    std::fs::write(path, s).with_context(|| format!("could not write {}", path.display()))
+}
+
+/// Load statistics, starting afresh if none have been saved yet.
+pub fn load(path: &Path) -> anyhow::Result<Stats> {
+    let s = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Stats::default()),
+        Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+    };
+    toml::from_str(&s).with_context(|| format!("invalid statistics in {}", path.display()))
+}
+
+/// Load the statistics from their default location, starting afresh if they
+/// cannot be read.
+pub fn load_or_default() -> Stats {
+    default_path()
+        .and_then(|path| load(&path))
+        .unwrap_or_else(|e| {
+            eprintln!("{:#}", e);
+            Stats::default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let mut stats = Stats::default();
+        assert_eq!(0, stats.best("4x4"));
+        assert!(stats.record("4x4", 100));
+        assert!(!stats.record("4x4", 80));
+        assert!(stats.record("5x5", 20));
+        assert_eq!(100, stats.best("4x4"));
+
+        let s = toml::to_string(&stats).unwrap();
+        assert_eq!(stats, toml::from_str(&s).unwrap());
+    }
+}
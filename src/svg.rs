@@ -0,0 +1,242 @@
+//! Vector export of display lists. Tiles are rects with the same rounded
+//! corners as drawn by GL, and texts are made of the outlines of their
+//! glyphs, so that images look the same without the font.
+
+use crate::display_list::{self, by_depth, DisplayList, Text, Tile, Transform};
+use crate::text::layout;
+use anyhow::Context;
+use rusttype::{Font, OutlineBuilder, Scale};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A number with at most two decimals, without trailing zeros.
+fn num(v: f32) -> String {
+    let s = format!("{:.2}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn hex(colour: [f32; 3]) -> String {
+    let [r, g, b] = colour.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Path data of a glyph outline, translated to its position in the image.
+struct PathData {
+    d: String,
+    origin: (f32, f32),
+}
+
+impl PathData {
+    fn point(&mut self, x: f32, y: f32) {
+        let (x0, y0) = self.origin;
+        write!(self.d, " {} {}", num(x0 + x), num(y0 + y)).unwrap();
+    }
+}
+
+impl OutlineBuilder for PathData {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.d.push('M');
+        self.point(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.d.push('L');
+        self.point(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.d.push('Q');
+        self.point(x1, y1);
+        self.point(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.d.push('C');
+        self.point(x1, y1);
+        self.point(x2, y2);
+        self.point(x, y);
+    }
+
+    fn close(&mut self) {
+        self.d.push('Z');
+    }
+}
+
+/// Attribute drawing an element scaled and rotated around the centre of a
+/// rect, if it is.
+fn transform_attribute(transform: &Transform, rect: [u32; 4], height: u32) -> String {
+    if transform.scale == 1.0 && transform.rotation == 0.0 {
+        return String::new();
+    }
+    let (x, y) = display_list::centre(rect);
+    let (x, y) = (num(x), num(height as f32 - y));
+    // y points down, so counterclockwise turns are negative
+    format!(
+        r#" transform="translate({x} {y}) rotate({}) scale({}) translate(-{x} -{y})""#,
+        num(-transform.rotation.to_degrees()),
+        num(transform.scale),
+        x = x,
+        y = y
+    )
+}
+
+fn write_tile(out: &mut String, tile: &Tile, radius: f32, height: u32) {
+    let [x, y, w, h] = tile.rect;
+    let alpha = tile.alpha * tile.transform.opacity;
+    if alpha <= 0.0 || tile.transform.scale <= 0.0 || w == 0 || h == 0 {
+        return;
+    }
+    write!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}""#,
+        x,
+        height as i64 - (y + h) as i64,
+        w,
+        h
+    )
+    .unwrap();
+    if radius > 0.0 {
+        // the corners of the tile shader are ellipses in the unit square
+        let (rx, ry) = (radius * w as f32, radius * h as f32);
+        write!(out, r#" rx="{}" ry="{}""#, num(rx), num(ry)).unwrap();
+    }
+    write!(out, r#" fill="{}""#, hex(tile.colour)).unwrap();
+    if alpha < 1.0 {
+        write!(out, r#" fill-opacity="{}""#, num(alpha)).unwrap();
+    }
+    out.push_str(&transform_attribute(&tile.transform, tile.rect, height));
+    out.push_str("/>\n");
+}
+
+fn write_text(out: &mut String, font: &Font<'static>, text: &Text, height: u32) {
+    let transform = &text.transform;
+    if transform.opacity <= 0.0 || transform.scale <= 0.0 {
+        return;
+    }
+    let mut path = PathData {
+        d: String::new(),
+        origin: (0.0, 0.0),
+    };
+    for placed in layout(font, &text.text, text.rect, &text.style) {
+        // as drawn by GL, the bottom of the cell is at the descent
+        let [x, y, size, _] = placed.cell().map(|v| v as f32);
+        let scale = Scale::uniform(size);
+        let descent = font.v_metrics(scale).descent;
+        path.origin = (x, height as f32 - y + descent);
+        font.glyph(placed.c).scaled(scale).build_outline(&mut path);
+    }
+    if path.d.is_empty() {
+        return;
+    }
+    write!(out, r#"<path fill="{}""#, hex(text.colour)).unwrap();
+    if transform.opacity < 1.0 {
+        write!(out, r#" fill-opacity="{}""#, num(transform.opacity)).unwrap();
+    }
+    out.push_str(&transform_attribute(transform, text.rect, height));
+    writeln!(out, r#" d="{}"/>"#, path.d.trim()).unwrap();
+}
+
+/// Write a display list as an SVG document of the given size in pixels.
+pub fn render(list: &DisplayList, width: u32, height: u32, font: &Font<'static>) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="{}"/>"#,
+        width,
+        height,
+        hex(list.background)
+    )
+    .unwrap();
+    for layer in &list.layers {
+        let radius = if layer.rounded { list.radius } else { 0.0 };
+        for tile in by_depth(&layer.tiles) {
+            write_tile(&mut out, tile, radius, height);
+        }
+        for text in by_depth(&layer.texts) {
+            write_text(&mut out, font, text, height);
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+pub fn save(
+    list: &DisplayList,
+    width: u32,
+    height: u32,
+    font: &Font<'static>,
+    path: &Path,
+) -> anyhow::Result<()> {
+    std::fs::write(path, render(list, width, height, font))
+        .with_context(|| format!("could not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_list::Layer;
+    use crate::text::TextStyle;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn document() {
+        let list = DisplayList {
+            background: [0.0, 0.0, 1.0],
+            radius: 0.1,
+            layers: vec![Layer {
+                rounded: true,
+                tiles: vec![Tile {
+                    colour: [1.0, 0.5, 0.0],
+                    alpha: 0.5,
+                    rect: [10, 20, 30, 40],
+                    transform: Transform::IDENTITY,
+                }],
+                texts: vec![Text {
+                    rect: [10, 20, 30, 40],
+                    text: "2".to_string(),
+                    colour: [1.0; 3],
+                    style: TextStyle::TILE,
+                    transform: Transform {
+                        scale: 1.5,
+                        rotation: FRAC_PI_2,
+                        opacity: 0.25,
+                        depth: 0.0,
+                    },
+                }],
+            }],
+        };
+        let svg = render(&list, 100, 80, &crate::font::fallback());
+
+        assert!(svg.starts_with("<svg "), "{}", svg);
+        assert!(svg.contains(r##"<rect width="100" height="80" fill="#0000ff"/>"##));
+        // flipped, with corners and opacity
+        assert!(svg.contains(
+            r##"<rect x="10" y="20" width="30" height="40" rx="3" ry="4" fill="#ff8000" fill-opacity="0.5"/>"##
+        ));
+        // turned around the centre of the tile, at (25, 40) once flipped
+        assert!(svg.contains(
+            r##"<path fill="#ffffff" fill-opacity="0.25" transform="translate(25 40) rotate(-90) scale(1.5) translate(-25 -40)" d="M"##
+        ));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!("1.5", num(1.5));
+        assert_eq!("2", num(2.0));
+        assert_eq!("0.33", num(1.0 / 3.0));
+        assert_eq!("0", num(-0.001));
+    }
+}
@@ -0,0 +1,386 @@
+#[cfg(feature = "render")]
+use rusttype::{Font, Scale};
+
+/// Horizontal alignment of lines of text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Centre,
+    Right,
+}
+
+/// Vertical alignment of a block of text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    /// Font size, as a fraction of the height of the rect.
+    pub size: f32,
+    /// Margin on all sides, as a fraction of the height of the rect.
+    pub padding: f32,
+    pub align: Align,
+    pub valign: VAlign,
+    /// Distance between baselines, relative to the font size.
+    pub line_spacing: f32,
+    /// Break lines between words to fit the width of the rect.
+    pub wrap: bool,
+    /// Shrink the font size until the text fits the rect.
+    pub fit: bool,
+}
+
+impl TextStyle {
+    /// Number on a tile.
+    pub const TILE: TextStyle = TextStyle {
+        size: 0.4,
+        padding: 0.08,
+        align: Align::Centre,
+        valign: VAlign::Middle,
+        line_spacing: 1.2,
+        wrap: false,
+        fit: true,
+    };
+
+    /// Message shown over the whole board.
+    pub const OVERLAY: TextStyle = TextStyle {
+        size: 0.15,
+        padding: 0.05,
+        align: Align::Centre,
+        valign: VAlign::Middle,
+        line_spacing: 1.2,
+        wrap: true,
+        fit: true,
+    };
+
+    /// Entry of a menu.
+    pub const ITEM: TextStyle = TextStyle {
+        size: 0.5,
+        padding: 0.1,
+        align: Align::Centre,
+        valign: VAlign::Middle,
+        line_spacing: 1.2,
+        wrap: false,
+        fit: true,
+    };
+
+    /// Name of a setting, on the left of its control.
+    pub const FIELD: TextStyle = TextStyle {
+        size: 0.4,
+        padding: 0.15,
+        align: Align::Left,
+        valign: VAlign::Middle,
+        line_spacing: 1.2,
+        wrap: false,
+        fit: true,
+    };
+
+    /// Value of a setting, on the right of its control.
+    pub const FIELD_VALUE: TextStyle = TextStyle {
+        align: Align::Right,
+        ..TextStyle::FIELD
+    };
+
+    /// Caption of a box in the HUD panel.
+    pub const LABEL: TextStyle = TextStyle {
+        size: 0.6,
+        padding: 0.1,
+        align: Align::Centre,
+        valign: VAlign::Bottom,
+        line_spacing: 1.2,
+        wrap: false,
+        fit: true,
+    };
+
+    /// Value of a box in the HUD panel.
+    pub const VALUE: TextStyle = TextStyle {
+        size: 0.6,
+        padding: 0.1,
+        align: Align::Centre,
+        valign: VAlign::Middle,
+        line_spacing: 1.2,
+        wrap: false,
+        fit: true,
+    };
+}
+
+/// Horizontal metrics of a font, relative to the font size.
+pub trait Metrics {
+    fn advance(&self, c: char) -> f32;
+    fn kerning(&self, a: char, b: char) -> f32;
+}
+
+#[cfg(feature = "render")]
+impl Metrics for Font<'_> {
+    fn advance(&self, c: char) -> f32 {
+        self.glyph(c)
+            .scaled(Scale::uniform(1.0))
+            .h_metrics()
+            .advance_width
+    }
+
+    fn kerning(&self, a: char, b: char) -> f32 {
+        self.pair_kerning(Scale::uniform(1.0), a, b)
+    }
+}
+
+/// Font size glyphs are rasterized at, before being scaled to the size of
+/// their cell.
+pub const GLYPH_SCALE: f32 = 100.0;
+
+/// A character placed in a rect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlacedChar {
+    pub c: char,
+    /// Bottom-left corner of the character cell.
+    pub pos: (f32, f32),
+    /// Font size in pixels.
+    pub size: f32,
+}
+
+impl PlacedChar {
+    /// The character cell in whole pixels, as drawn.
+    pub fn cell(&self) -> [u32; 4] {
+        [
+            self.pos.0.max(0.0) as u32,
+            self.pos.1.max(0.0) as u32,
+            self.size as u32,
+            self.size as u32,
+        ]
+    }
+}
+
+/// Width of a line of text, relative to the font size.
+pub fn measure(metrics: &impl Metrics, line: &str) -> f32 {
+    let mut width = 0.0;
+    let mut prev = None;
+    for c in line.chars() {
+        if let Some(p) = prev {
+            width += metrics.kerning(p, c);
+        }
+        width += metrics.advance(c);
+        prev = Some(c);
+    }
+    width
+}
+
+/// Split text into lines no wider than `max_width`, relative to the font
+/// size. Words that do not fit on a line on their own are not broken.
+fn wrap<'a>(metrics: &impl Metrics, text: &'a str, max_width: f32) -> Vec<&'a str> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut start = 0;
+        let mut end = 0;
+        for (i, word) in paragraph.split(' ').scan(0, |pos, word| {
+            let i = *pos;
+            *pos += word.len() + 1;
+            Some((i, word))
+        }) {
+            let candidate = &paragraph[start..i + word.len()];
+            if end > start && measure(metrics, candidate) > max_width {
+                lines.push(&paragraph[start..end]);
+                start = i;
+            }
+            end = i + word.len();
+        }
+        lines.push(&paragraph[start..end]);
+    }
+    lines
+}
+
+/// Lay out text in a rect, given as `[x, y, width, height]` with the origin
+/// at the bottom left.
+pub fn layout(
+    metrics: &impl Metrics,
+    text: &str,
+    rect: [u32; 4],
+    style: &TextStyle,
+) -> Vec<PlacedChar> {
+    let [x0, y0, w, h] = rect.map(|v| v as f32);
+    let padding = style.padding * h;
+    let avail = ((w - 2.0 * padding).max(0.0), (h - 2.0 * padding).max(0.0));
+
+    let mut size = style.size * h;
+    let mut lines;
+    loop {
+        lines = if style.wrap && size > 0.0 {
+            wrap(metrics, text, avail.0 / size)
+        } else {
+            text.split('\n').collect()
+        };
+        if !style.fit {
+            break;
+        }
+
+        let widest = lines
+            .iter()
+            .map(|line| measure(metrics, line))
+            .fold(0.0, f32::max);
+        let height = (lines.len() - 1) as f32 * style.line_spacing + 1.0;
+        let fit = (avail.0 / widest).min(avail.1 / height);
+        // stop when shrinking no longer changes anything significantly
+        if size <= fit * 1.001 || size < 1.0 {
+            break;
+        }
+        size = fit;
+    }
+
+    let height = ((lines.len() - 1) as f32 * style.line_spacing + 1.0) * size;
+    let top = match style.valign {
+        VAlign::Top => y0 + h - padding,
+        VAlign::Middle => y0 + (h + height) / 2.0,
+        VAlign::Bottom => y0 + padding + height,
+    };
+
+    let mut result = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let width = measure(metrics, line) * size;
+        let mut x = match style.align {
+            Align::Left => x0 + padding,
+            Align::Centre => x0 + (w - width) / 2.0,
+            Align::Right => x0 + w - padding - width,
+        };
+        let y = top - size - i as f32 * style.line_spacing * size;
+
+        let mut prev = None;
+        for c in line.chars() {
+            if let Some(p) = prev {
+                x += metrics.kerning(p, c) * size;
+            }
+            if !c.is_whitespace() {
+                result.push(PlacedChar {
+                    c,
+                    pos: (x, y),
+                    size,
+                });
+            }
+            x += metrics.advance(c) * size;
+            prev = Some(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monospace font, where "AV" is kerned.
+    struct Mono;
+
+    impl Metrics for Mono {
+        fn advance(&self, _c: char) -> f32 {
+            0.5
+        }
+
+        fn kerning(&self, a: char, b: char) -> f32 {
+            if (a, b) == ('A', 'V') {
+                -0.25
+            } else {
+                0.0
+            }
+        }
+    }
+
+    const PLAIN: TextStyle = TextStyle {
+        size: 0.5,
+        padding: 0.0,
+        align: Align::Left,
+        valign: VAlign::Top,
+        line_spacing: 1.0,
+        wrap: false,
+        fit: false,
+    };
+
+    fn positions(chars: &[PlacedChar]) -> Vec<(f32, f32)> {
+        chars.iter().map(|c| c.pos).collect()
+    }
+
+    #[test]
+    fn kerning() {
+        assert_eq!(1.0, measure(&Mono, "AB"));
+        assert_eq!(0.75, measure(&Mono, "AV"));
+
+        let chars = layout(&Mono, "AVA", [0, 0, 100, 100], &PLAIN);
+        assert_eq!(
+            vec![(0.0, 50.0), (12.5, 50.0), (37.5, 50.0)],
+            positions(&chars)
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let centre = TextStyle {
+            align: Align::Centre,
+            valign: VAlign::Middle,
+            ..PLAIN
+        };
+        let chars = layout(&Mono, "ab", [10, 10, 100, 100], &centre);
+        assert_eq!(vec![(35.0, 35.0), (60.0, 35.0)], positions(&chars));
+
+        let right = TextStyle {
+            align: Align::Right,
+            valign: VAlign::Bottom,
+            ..PLAIN
+        };
+        let chars = layout(&Mono, "ab", [10, 10, 100, 100], &right);
+        assert_eq!(vec![(60.0, 10.0), (85.0, 10.0)], positions(&chars));
+    }
+
+    #[test]
+    fn multiline() {
+        let style = TextStyle {
+            size: 0.25,
+            line_spacing: 1.5,
+            ..PLAIN
+        };
+        let chars = layout(&Mono, "a b\nc", [0, 0, 100, 100], &style);
+        // spaces are not placed
+        assert_eq!(
+            vec![(0.0, 75.0), (25.0, 75.0), (0.0, 37.5)],
+            positions(&chars)
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(
+            vec!["one two", "three", "four"],
+            wrap(&Mono, "one two three four", 4.0)
+        );
+        assert_eq!(vec!["a", "", "b c"], wrap(&Mono, "a\n\nb c", 10.0));
+        assert_eq!(vec!["toolongword"], wrap(&Mono, "toolongword", 2.0));
+
+        let style = TextStyle {
+            size: 0.1,
+            wrap: true,
+            ..PLAIN
+        };
+        let chars = layout(&Mono, "aa bb", [0, 0, 15, 100], &style);
+        assert_eq!(
+            vec![(0.0, 90.0), (5.0, 90.0), (0.0, 80.0), (5.0, 80.0)],
+            positions(&chars)
+        );
+    }
+
+    #[test]
+    fn fit() {
+        let style = TextStyle {
+            size: 1.0,
+            fit: true,
+            ..PLAIN
+        };
+        // 8 characters at half the font size each fit in 100 pixels at size 25
+        let chars = layout(&Mono, "abcdefgh", [0, 0, 100, 100], &style);
+        assert_eq!(8, chars.len());
+        assert!(chars.iter().all(|c| (c.size - 25.0).abs() < 1e-3));
+
+        // short text is not enlarged
+        let style = TextStyle { size: 0.1, ..style };
+        let chars = layout(&Mono, "a", [0, 0, 100, 100], &style);
+        assert_eq!(10.0, chars[0].size);
+    }
+}
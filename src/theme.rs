@@ -0,0 +1,211 @@
+use crate::game::Value;
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::path::Path;
+
+/// An RGB colour, written as `"#rrggbb"` in theme files.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Colour(pub [f32; 3]);
+
+impl TryFrom<String> for Colour {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        let hex = s
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.is_ascii())
+            .ok_or_else(|| format!("invalid colour `{}`, expected #rrggbb", s))?;
+        let mut rgb = [0.0; 3];
+        for (i, c) in rgb.iter_mut().enumerate() {
+            let byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .map_err(|_| format!("invalid colour `{}`", s))?;
+            *c = byte as f32 / 255.0;
+        }
+        Ok(Colour(rgb))
+    }
+}
+
+/// Colours indexed by tile value, starting from the 2 tile. Values past the
+/// end of the palette use its last colour.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Palette {
+    List(Vec<Colour>),
+    /// Evenly spaced stops, interpolated over the given number of values.
+    Gradient {
+        gradient: Vec<Colour>,
+        steps: usize,
+    },
+}
+
+impl Palette {
+    pub fn colour(&self, value: Value) -> [f32; 3] {
+        let index = (value as usize).saturating_sub(1);
+        match self {
+            Palette::List(colours) => colours
+                .get(index)
+                .or(colours.last())
+                .map_or([1.0; 3], |c| c.0),
+            Palette::Gradient { gradient, steps } => {
+                if gradient.len() < 2 || *steps < 2 {
+                    return gradient.first().map_or([1.0; 3], |c| c.0);
+                }
+                let t = index.min(steps - 1) as f32 / (steps - 1) as f32;
+                let x = t * (gradient.len() - 1) as f32;
+                let i = (x as usize).min(gradient.len() - 2);
+                let f = x - i as f32;
+                let (a, b) = (gradient[i].0, gradient[i + 1].0);
+                [0, 1, 2].map(|k| a[k] + (b[k] - a[k]) * f)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Colour of the window behind the board.
+    pub background: Colour,
+    /// Colour of cells with no tile.
+    pub empty: Colour,
+    /// Colour of the screen shown over the board, e.g. when the game is over.
+    pub overlay: Colour,
+    pub overlay_text: Colour,
+    pub tiles: Palette,
+    /// Text colour of each tile.
+    pub text: Palette,
+}
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("default", include_str!("../themes/default.toml")),
+    ("classic", include_str!("../themes/classic.toml")),
+    ("mono", include_str!("../themes/mono.toml")),
+];
+
+pub fn default_theme() -> String {
+    "default".to_string()
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin("default").unwrap()
+    }
+}
+
+impl Theme {
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN.iter().map(|(name, _)| *name)
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let (_, s) = BUILTIN.iter().find(|(n, _)| *n == name)?;
+        Some(toml::from_str(s).expect("invalid built-in theme"))
+    }
+
+    /// Load a theme by name. The name can be that of a built-in theme, of a
+    /// theme in the `themes` subdirectory of the configuration directory, or
+    /// a path to a theme file.
+    pub fn load(name: &str) -> anyhow::Result<Theme> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = Path::new(name);
+        let path = if path.extension().is_some() || path.components().count() > 1 {
+            path.to_path_buf()
+        } else {
+            let unknown = || {
+                anyhow!(
+                    "unknown theme `{}`, built-in themes are: {}",
+                    name,
+                    Self::builtin_names().collect::<Vec<_>>().join(", ")
+                )
+            };
+            let mut path = dirs::config_dir().ok_or_else(unknown)?;
+            path.push("iron");
+            path.push("themes");
+            path.push(format!("{}.toml", name));
+            if !path.exists() {
+                return Err(unknown());
+            }
+            path
+        };
+
+        let s = std::fs::read_to_string(&path)
+            .with_context(|| format!("could not read theme {}", path.display()))?;
+        toml::from_str(&s).with_context(|| format!("invalid theme {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins() {
+        for name in Theme::builtin_names() {
+            assert!(Theme::builtin(name).is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        let err = Theme::load("no-such-theme").unwrap_err().to_string();
+        assert!(
+            err.contains("built-in themes are: default, classic"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn colour() {
+        let c: Colour = "#ff8000".to_string().try_into().unwrap();
+        assert_eq!([1.0, 128.0 / 255.0, 0.0], c.0);
+        assert!(Colour::try_from("ff8000".to_string()).is_err());
+        assert!(Colour::try_from("#ff80zz".to_string()).is_err());
+        // six bytes, but not six hex digits
+        assert!(Colour::try_from("#aébbb".to_string()).is_err());
+        let theme = toml::from_str::<Theme>(
+            r##"
+            background = "#aébbb"
+            empty = "#000000"
+            overlay = "#000000"
+            overlay_text = "#000000"
+            tiles = ["#000000"]
+            text = ["#000000"]
+            "##,
+        );
+        assert!(theme.is_err());
+    }
+
+    #[test]
+    fn no_wrapping() {
+        let theme = Theme::builtin("classic").unwrap();
+        // 2 and 8192 have different colours
+        assert_ne!(theme.tiles.colour(1), theme.tiles.colour(13));
+        assert_eq!(theme.tiles.colour(12), theme.tiles.colour(13));
+        assert_eq!(theme.text.colour(1), theme.text.colour(2));
+        assert_ne!(theme.text.colour(2), theme.text.colour(3));
+    }
+
+    #[test]
+    fn gradient() {
+        let palette: Palette = toml::from_str::<Theme>(
+            r##"
+            background = "#000000"
+            empty = "#000000"
+            overlay = "#000000"
+            overlay_text = "#000000"
+            tiles = { gradient = ["#000000", "#ffffff"], steps = 3 }
+            text = ["#000000"]
+            "##,
+        )
+        .unwrap()
+        .tiles;
+        assert_eq!([0.0; 3], palette.colour(1));
+        assert_eq!([0.5; 3], palette.colour(2));
+        assert_eq!([1.0; 3], palette.colour(3));
+        assert_eq!([1.0; 3], palette.colour(10));
+    }
+}
@@ -1,52 +1,77 @@
+use crate::display_list::{by_depth, Tile};
 use crate::graphics::util::rect;
-use crate::graphics::{Object, Program, Quad, VertexArray, VertexBuffer};
-use std::cell::RefCell;
+use crate::graphics::{Object, Program, Quad, VertexArray, VertexBuffer, VertexBufferRef};
+use anyhow::Context;
 use std::rc::Rc;
 
-pub fn default_tile_radius() -> f32 {
-    0.03
-}
-
-pub struct Tiles {
-    obj: Object,
-    quad: Rc<RefCell<Quad>>,
+/// Instance data of one draw call, kept across frames so that tiles that
+/// did not change are not uploaded again.
+struct Batch {
+    vao: VertexArray,
     rects: VertexBuffer<u32>,
     colours: VertexBuffer<f32>,
+    /// Scale, rotation and opacity.
+    transforms: VertexBuffer<f32>,
 }
 
-#[derive(Debug)]
-pub struct Tile {
-    pub colour: [f32; 3],
-    pub rect: [u32; 4],
+impl Batch {
+    fn new(gl: Rc<glow::Context>, quad: Rc<VertexBufferRef>) -> Self {
+        let mut vao = VertexArray::new(gl.clone());
+        vao.add_buffer(quad);
+
+        let rects = VertexBuffer::per_instance(gl.clone(), 4);
+        vao.add_buffer(rects.to_ref());
+
+        let colours = VertexBuffer::per_instance(gl.clone(), 4);
+        vao.add_buffer(colours.to_ref());
+
+        let transforms = VertexBuffer::per_instance(gl, 3);
+        vao.add_buffer(transforms.to_ref());
+
+        Batch {
+            vao,
+            rects,
+            colours,
+            transforms,
+        }
+    }
+}
+
+pub struct Tiles {
+    gl: Rc<glow::Context>,
+    obj: Object,
+    quad: Rc<VertexBufferRef>,
+    batches: Vec<Batch>,
 }
 
 impl Tiles {
-    pub fn new(gl: Rc<glow::Context>, quad: Rc<RefCell<Quad>>, radius: f32, alpha: f32) -> Self {
+    pub fn new(
+        gl: Rc<glow::Context>,
+        quad: &Quad,
+        radius: f32,
+        alpha: f32,
+    ) -> anyhow::Result<Self> {
         let mut program = Program::new(
             gl.clone(),
             include_bytes!("../shaders/tile.v.glsl"),
             include_bytes!("../shaders/tile.f.glsl"),
-        );
+        )
+        .context("could not build the tile shaders")?;
         program.set_uniform("radius", radius);
         program.set_uniform("alpha", alpha);
 
-        let mut vao = VertexArray::new(gl.clone());
-        vao.add_buffer(quad.borrow().vbo());
-
-        let rects: VertexBuffer<u32> = VertexBuffer::new(gl.clone(), 2);
-        vao.add_buffer(rects.to_ref());
-
-        let colours = VertexBuffer::new(gl.clone(), 3);
-        vao.add_buffer(colours.to_ref());
-
-        let obj = Object::new(gl, vao, quad.borrow().ebo(), None, program);
+        let obj = Object::new(gl.clone(), None, program);
 
-        Tiles {
+        Ok(Tiles {
+            gl,
             obj,
-            quad,
-            rects,
-            colours,
-        }
+            quad: quad.vbo(),
+            batches: Vec::new(),
+        })
+    }
+
+    pub fn set_radius(&mut self, radius: f32) {
+        self.obj.program().set_uniform("radius", radius);
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -55,32 +80,36 @@ impl Tiles {
             .set_uniform("viewport", rect(0, 0, width as i32, height as i32));
     }
 
-    pub fn update<'a>(&mut self, tiles: impl Iterator<Item = &'a Tile>) {
-        self.rects.buffer.truncate(0);
-        self.colours.buffer.truncate(0);
+    /// Draw tiles, using the buffers of the given batch. Drawing the same
+    /// kind of tiles with the same batch every frame avoids uploads. Tiles
+    /// are drawn by increasing depth, without a depth buffer.
+    pub fn update<'a>(&mut self, batch: usize, tiles: impl Iterator<Item = &'a Tile>) {
+        while self.batches.len() <= batch {
+            self.batches
+                .push(Batch::new(self.gl.clone(), self.quad.clone()));
+        }
+        let batch = &mut self.batches[batch];
+        batch.rects.buffer.truncate(0);
+        batch.colours.buffer.truncate(0);
+        batch.transforms.buffer.truncate(0);
         let mut count = 0;
 
-        for tile in tiles {
-            for _ in 0..4 {
-                self.colours.buffer.extend_from_slice(&tile.colour);
-            }
-
-            self.rects.buffer.push(tile.rect[0]);
-            self.rects.buffer.push(tile.rect[1]);
-            self.rects.buffer.push(tile.rect[0] + tile.rect[2]);
-            self.rects.buffer.push(tile.rect[1]);
-            self.rects.buffer.push(tile.rect[0]);
-            self.rects.buffer.push(tile.rect[1] + tile.rect[3]);
-            self.rects.buffer.push(tile.rect[0] + tile.rect[2]);
-            self.rects.buffer.push(tile.rect[1] + tile.rect[3]);
-
+        for tile in by_depth(tiles) {
+            batch.colours.buffer.extend_from_slice(&tile.colour);
+            batch.colours.buffer.push(tile.alpha);
+            batch.rects.buffer.extend_from_slice(&tile.rect);
+            let t = &tile.transform;
+            batch
+                .transforms
+                .buffer
+                .extend_from_slice(&[t.scale, t.rotation, t.opacity]);
             count += 1;
         }
 
-        self.rects.update(glow::STATIC_DRAW);
-        self.colours.update(glow::STATIC_DRAW);
+        batch.rects.update(glow::DYNAMIC_DRAW);
+        batch.colours.update(glow::DYNAMIC_DRAW);
+        batch.transforms.update(glow::DYNAMIC_DRAW);
 
-        self.quad.borrow_mut().ensure(count);
-        self.obj.render(count);
+        self.obj.render(&batch.vao, count);
     }
 }
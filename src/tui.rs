@@ -0,0 +1,394 @@
+//! Drawing of display lists in a terminal, with ANSI true colour. Each
+//! character cell holds two square-ish pixels: the upper half block `▀`
+//! shows the top one in the foreground colour, and the bottom one in the
+//! background colour. Texts take whole cells, so they are scaled and faded
+//! with their transform but not rotated.
+
+use crate::display_list::{self, by_depth, DisplayList, Text, Tile, Transform};
+use crate::text::{Align, VAlign};
+use crossterm::cursor::MoveTo;
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::QueueableCommand;
+use std::io::{self, Write};
+
+const UPPER_HALF: char = '▀';
+
+/// A character cell of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: [u8; 3],
+    pub bg: [u8; 3],
+}
+
+/// The contents of the terminal, with rows from top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Screen {
+    pub cols: u16,
+    pub rows: u16,
+    cells: Vec<Cell>,
+}
+
+/// Size in pixels of display lists drawn on `cols` x `rows` cells.
+pub fn pixel_size(cols: u16, rows: u16) -> (u32, u32) {
+    (cols as u32, rows as u32 * 2)
+}
+
+/// Pixels and texts being drawn, with the origin of pixels at the bottom
+/// left like display lists, and rows of texts from the top.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 3]>,
+    /// Character and colour of cells covered by text.
+    glyphs: Vec<Option<(char, [f32; 3])>>,
+}
+
+impl Canvas {
+    fn new(cols: u16, rows: u16, background: [f32; 3]) -> Self {
+        let (width, height) = pixel_size(cols, rows);
+        Self {
+            width,
+            height,
+            pixels: vec![background; (width * height) as usize],
+            glyphs: vec![None; cols as usize * rows as usize],
+        }
+    }
+
+    fn cell(&self, col: u32, row: u32) -> usize {
+        (row * self.width + col) as usize
+    }
+
+    /// Blend a tile over the pixels it covers, and over the text of cells
+    /// whose pixels it covers.
+    fn fill(&mut self, tile: &Tile, radius: f32) {
+        let [x, y, w, h] = tile.rect;
+        if w == 0 || h == 0 {
+            return;
+        }
+        let transform = &tile.transform;
+        let centre = display_list::centre(tile.rect);
+        let inside = |px: u32, py: u32| {
+            let p = (px as f32 + 0.5, py as f32 + 0.5);
+            let Some((tx, ty)) = transform.invert(centre, p) else {
+                return false;
+            };
+            // in the unit square of the tile
+            let u = (tx - x as f32) / w as f32;
+            let v = (ty - y as f32) / h as f32;
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                return false;
+            }
+            if radius <= 0.0 {
+                return true;
+            }
+            // same distance as the tile shader
+            let dx = ((u - 0.5).abs() - (0.5 - radius)).max(0.0);
+            let dy = ((v - 0.5).abs() - (0.5 - radius)).max(0.0);
+            (dx * dx + dy * dy).sqrt() <= radius
+        };
+        let alpha = tile.alpha * transform.opacity;
+        let blend = |dst: &mut [f32; 3]| {
+            for (d, s) in dst.iter_mut().zip(tile.colour) {
+                *d += (s - *d) * alpha;
+            }
+        };
+
+        let [x0, y0, x1, y1] = transform.bounds(centre, display_list::edges(tile.rect));
+        let (x, y) = (x0.max(0.0) as u32, y0.max(0.0) as u32);
+        let x1 = (x1.max(0.0).ceil() as u32).min(self.width);
+        let y1 = (y1.max(0.0).ceil() as u32).min(self.height);
+        if x >= x1 || y >= y1 {
+            return;
+        }
+        for py in y..y1 {
+            for px in x..x1 {
+                if inside(px, py) {
+                    blend(&mut self.pixels[(py * self.width + px) as usize]);
+                }
+            }
+        }
+
+        // a cell holds the pixels 2 * row and 2 * row + 1 from the top
+        let top = self.height - y1;
+        let bottom = self.height - y;
+        for row in top / 2..bottom.div_ceil(2) {
+            for col in x..x1 {
+                let covered = [2 * row, 2 * row + 1]
+                    .into_iter()
+                    .map(|r| self.height - 1 - r)
+                    .any(|py| (y..y1).contains(&py) && inside(col, py));
+                let index = self.cell(col, row);
+                if let (true, Some((_, colour))) = (covered, &mut self.glyphs[index]) {
+                    blend(colour);
+                }
+            }
+        }
+    }
+
+    /// Write a text in its rect, scaled around its centre, with one character
+    /// per cell.
+    fn print(&mut self, text: &Text) {
+        let transform = &text.transform;
+        if transform.opacity <= 0.0 || transform.scale <= 0.0 {
+            return;
+        }
+        let [x0, y0, x1, y1] = Transform {
+            rotation: 0.0,
+            ..*transform
+        }
+        .bounds(
+            display_list::centre(text.rect),
+            display_list::edges(text.rect),
+        )
+        .map(|v| v.max(0.0).round() as u32);
+        let [x, y, w, h] = [x0, y0, x1 - x0, y1 - y0];
+        let rows = self.height / 2;
+        let top = self.height.saturating_sub(y + h).div_ceil(2);
+        let bottom = (self.height.saturating_sub(y) / 2).min(rows);
+        let available = bottom.saturating_sub(top) as usize;
+        let pad = (text.style.padding * h as f32) as u32;
+
+        let lines = if text.style.wrap {
+            wrap(&text.text, w.saturating_sub(2 * pad) as usize)
+        } else {
+            text.text.split('\n').map(str::to_string).collect()
+        };
+        let lines = &lines[..lines.len().min(available.max(1))];
+        let first = match text.style.valign {
+            VAlign::Top => top,
+            VAlign::Middle => top + (available.saturating_sub(lines.len()) / 2) as u32,
+            VAlign::Bottom => bottom.saturating_sub(lines.len() as u32),
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            let row = first + i as u32;
+            if row >= rows {
+                break;
+            }
+            let chars = line.chars().take(w as usize).collect::<Vec<_>>();
+            let len = chars.len() as u32;
+            let start = match text.style.align {
+                Align::Left => x + pad.min(w - len),
+                Align::Centre => x + (w - len) / 2,
+                Align::Right => x + w - len - pad.min(w - len),
+            };
+            for (col, &c) in (start..self.width).zip(&chars) {
+                if !c.is_whitespace() {
+                    // faded into the pixels of the cell
+                    let below = [0, 1].map(|i| {
+                        let py = self.height - 1 - (2 * row + i);
+                        self.pixels[(py * self.width + col) as usize]
+                    });
+                    let colour = [0, 1, 2].map(|i| {
+                        let bg = (below[0][i] + below[1][i]) / 2.0;
+                        bg + (text.colour[i] - bg) * transform.opacity
+                    });
+                    let index = self.cell(col, row);
+                    self.glyphs[index] = Some((c, colour));
+                }
+            }
+        }
+    }
+}
+
+/// Break lines between words to fit `width` characters.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn rgb(colour: [f32; 3]) -> [u8; 3] {
+    colour.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+impl Screen {
+    /// Draw a display list computed for the pixel size of the terminal.
+    pub fn draw(list: &DisplayList, cols: u16, rows: u16) -> Self {
+        let mut canvas = Canvas::new(cols, rows, list.background);
+        for layer in &list.layers {
+            let radius = if layer.rounded { list.radius } else { 0.0 };
+            for tile in by_depth(&layer.tiles) {
+                canvas.fill(tile, radius);
+            }
+            for text in by_depth(&layer.texts) {
+                canvas.print(text);
+            }
+        }
+
+        let height = canvas.height;
+        let pixel = |col: u32, row: u32| canvas.pixels[(row * canvas.width + col) as usize];
+        let mut cells = Vec::with_capacity(canvas.glyphs.len());
+        for row in 0..rows as u32 {
+            for col in 0..cols as u32 {
+                let top = rgb(pixel(col, height - 1 - 2 * row));
+                let bottom = rgb(pixel(col, height - 2 - 2 * row));
+                cells.push(match canvas.glyphs[canvas.cell(col, row)] {
+                    Some((ch, colour)) => Cell {
+                        ch,
+                        fg: rgb(colour),
+                        bg: [0, 1, 2].map(|i| ((top[i] as u16 + bottom[i] as u16) / 2) as u8),
+                    },
+                    None if top == bottom => Cell {
+                        ch: ' ',
+                        fg: bottom,
+                        bg: bottom,
+                    },
+                    None => Cell {
+                        ch: UPPER_HALF,
+                        fg: top,
+                        bg: bottom,
+                    },
+                });
+            }
+        }
+        Self { cols, rows, cells }
+    }
+
+    pub fn cell(&self, col: u16, row: u16) -> Cell {
+        self.cells[row as usize * self.cols as usize + col as usize]
+    }
+
+    /// Write the screen to the terminal. Only cells that differ from
+    /// `previous`, if it has the same size, are written.
+    pub fn write(&self, out: &mut impl Write, previous: Option<&Screen>) -> io::Result<()> {
+        let previous = previous.filter(|p| (p.cols, p.rows) == (self.cols, self.rows));
+        let mut colours = None;
+        let mut cursor = None;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let cell = self.cell(col, row);
+                if previous.is_some_and(|p| p.cell(col, row) == cell) {
+                    continue;
+                }
+                if cursor != Some((col, row)) {
+                    out.queue(MoveTo(col, row))?;
+                }
+                if colours != Some((cell.fg, cell.bg)) {
+                    let [r, g, b] = cell.fg;
+                    out.queue(SetForegroundColor(Color::Rgb { r, g, b }))?;
+                    let [r, g, b] = cell.bg;
+                    out.queue(SetBackgroundColor(Color::Rgb { r, g, b }))?;
+                    colours = Some((cell.fg, cell.bg));
+                }
+                out.queue(Print(cell.ch))?;
+                cursor = Some((col + 1, row));
+            }
+        }
+        out.queue(ResetColor)?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_list::Layer;
+    use crate::text::TextStyle;
+
+    const BLACK: [f32; 3] = [0.0; 3];
+    const WHITE: [f32; 3] = [1.0; 3];
+    const RED: [f32; 3] = [1.0, 0.0, 0.0];
+
+    fn list(tiles: Vec<Tile>, texts: Vec<Text>) -> DisplayList {
+        DisplayList {
+            background: BLACK,
+            radius: 0.0,
+            layers: vec![Layer {
+                rounded: false,
+                tiles,
+                texts,
+            }],
+        }
+    }
+
+    #[test]
+    fn half_blocks() {
+        // a red tile over the bottom three pixels of a 2x2 terminal
+        let tile = Tile {
+            colour: RED,
+            alpha: 1.0,
+            rect: [0, 0, 1, 3],
+            transform: Transform::IDENTITY,
+        };
+        let screen = Screen::draw(&list(vec![tile], Vec::new()), 2, 2);
+
+        let cell = screen.cell(0, 0);
+        assert_eq!(
+            (UPPER_HALF, [0, 0, 0], [255, 0, 0]),
+            (cell.ch, cell.fg, cell.bg)
+        );
+        assert_eq!(' ', screen.cell(0, 1).ch);
+        assert_eq!([255, 0, 0], screen.cell(0, 1).bg);
+        assert_eq!([0, 0, 0], screen.cell(1, 1).bg);
+    }
+
+    #[test]
+    fn texts() {
+        let text = Text {
+            rect: [0, 0, 8, 6],
+            text: "128".to_string(),
+            colour: WHITE,
+            style: TextStyle::TILE,
+            transform: Transform::IDENTITY,
+        };
+        let overlay = Tile {
+            colour: BLACK,
+            alpha: 0.5,
+            rect: [0, 0, 8, 2],
+            transform: Transform::IDENTITY,
+        };
+        let mut list = list(Vec::new(), vec![text]);
+        list.layers.push(Layer {
+            tiles: vec![overlay],
+            ..Layer::default()
+        });
+        let screen = Screen::draw(&list, 8, 3);
+
+        // centred on the middle row, and dimmed where the overlay covers it
+        let row = (0..8).map(|col| screen.cell(col, 1).ch).collect::<String>();
+        assert_eq!("  128   ", row);
+        assert_eq!([255; 3], screen.cell(3, 1).fg);
+
+        list.layers[1].tiles[0].rect = [0, 0, 8, 4];
+        let screen = Screen::draw(&list, 8, 3);
+        assert_eq!([128; 3], screen.cell(3, 1).fg);
+    }
+
+    #[test]
+    fn wrap_words() {
+        assert_eq!(vec!["New", "game?", "(y/n)"], wrap("New game? (y/n)", 6));
+        assert_eq!(vec!["New game?", "(y/n)"], wrap("New game? (y/n)", 9));
+    }
+
+    #[test]
+    fn write_changes() {
+        let tile = Tile {
+            colour: RED,
+            alpha: 1.0,
+            rect: [0, 0, 2, 2],
+            transform: Transform::IDENTITY,
+        };
+        let screen = Screen::draw(&list(vec![tile], Vec::new()), 2, 1);
+        let mut out = Vec::new();
+        screen.write(&mut out, None).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("48;2;255;0;0"));
+
+        let mut out = Vec::new();
+        screen.write(&mut out, Some(&screen)).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains(' '));
+    }
+}
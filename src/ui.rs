@@ -0,0 +1,401 @@
+/// What a widget is, and the value it holds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// Static text.
+    Label,
+    /// Static text with a caption above it, as in the HUD panel.
+    Value(String),
+    Button,
+    Toggle(bool),
+    /// One of `count` evenly spaced positions.
+    Slider {
+        index: usize,
+        count: usize,
+    },
+    /// A row of items, one of which is selected.
+    List {
+        items: Vec<String>,
+        selected: usize,
+    },
+}
+
+/// A widget, identified by `id` across rebuilds of the UI. Toggles, sliders
+/// and lists show their text on the left of the rect and their control on
+/// the right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Widget<Id> {
+    pub id: Id,
+    pub rect: [u32; 4],
+    pub text: String,
+    pub kind: Kind,
+}
+
+impl<Id> Widget<Id> {
+    pub fn new(id: Id, rect: [u32; 4], text: impl Into<String>, kind: Kind) -> Self {
+        Self {
+            id,
+            rect,
+            text: text.into(),
+            kind,
+        }
+    }
+
+    /// Whether the widget reacts to the mouse and can take keyboard focus.
+    pub fn interactive(&self) -> bool {
+        !matches!(self.kind, Kind::Label | Kind::Value(_))
+    }
+
+    /// Part of the rect taken by the control of a toggle, slider or list.
+    pub fn control(&self) -> [u32; 4] {
+        let [x, y, w, h] = self.rect;
+        match self.kind {
+            Kind::Toggle(_) | Kind::Slider { .. } | Kind::List { .. } => {
+                let label = w * 2 / 5;
+                [x + label, y, w - label, h]
+            }
+            _ => self.rect,
+        }
+    }
+
+    /// Value of a slider or list at a horizontal position, if it is within
+    /// the control.
+    fn value_at(&self, x: f32) -> Option<usize> {
+        let [cx, _, cw, _] = self.control().map(|v| v as f32);
+        let t = ((x - cx) / cw).clamp(0.0, 1.0);
+        match &self.kind {
+            Kind::Slider { count, .. } if *count > 0 => {
+                Some((t * (count - 1) as f32).round() as usize)
+            }
+            Kind::List { items, .. } if !items.is_empty() => {
+                Some(((t * items.len() as f32) as usize).min(items.len() - 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Interaction state of a widget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Normal,
+    Hover,
+    Pressed,
+}
+
+/// Something the player did to a widget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<Id> {
+    Clicked(Id),
+    Toggled(Id, bool),
+    /// A slider was moved, or a list item selected.
+    Changed(Id, usize),
+}
+
+/// A set of widgets, with the state of the mouse and keyboard focus. The
+/// widgets can be replaced at any time, e.g. every frame, while hover, press
+/// and focus are kept for widgets with the same id.
+#[derive(Debug)]
+pub struct Ui<Id> {
+    widgets: Vec<Widget<Id>>,
+    hover: Option<Id>,
+    pressed: Option<Id>,
+    focus: Option<Id>,
+}
+
+impl<Id> Default for Ui<Id> {
+    fn default() -> Self {
+        Self {
+            widgets: Vec::new(),
+            hover: None,
+            pressed: None,
+            focus: None,
+        }
+    }
+}
+
+impl<Id: Copy + PartialEq> Ui<Id> {
+    pub fn set(&mut self, widgets: Vec<Widget<Id>>) {
+        let keep =
+            |id: Option<Id>| id.filter(|&id| widgets.iter().any(|w| w.interactive() && w.id == id));
+        self.hover = keep(self.hover);
+        self.pressed = keep(self.pressed);
+        self.focus = keep(self.focus);
+        self.widgets = widgets;
+    }
+
+    /// Widgets to draw, with their status and whether they have focus.
+    pub fn widgets(&self) -> impl Iterator<Item = (&Widget<Id>, Status, bool)> {
+        self.widgets.iter().map(|w| {
+            let status = if w.interactive() && self.pressed == Some(w.id) {
+                Status::Pressed
+            } else if w.interactive() && self.hover == Some(w.id) {
+                Status::Hover
+            } else {
+                Status::Normal
+            };
+            (w, status, w.interactive() && self.focus == Some(w.id))
+        })
+    }
+
+    fn get(&self, id: Id) -> Option<&Widget<Id>> {
+        self.widgets.iter().find(|w| w.interactive() && w.id == id)
+    }
+
+    /// Interactive widget at a position, with the origin at the bottom left.
+    pub fn hit(&self, (x, y): (f32, f32)) -> Option<Id> {
+        self.widgets
+            .iter()
+            .rev()
+            .find(|w| {
+                let [rx, ry, rw, rh] = w.rect.map(|v| v as f32);
+                w.interactive() && x >= rx && x < rx + rw && y >= ry && y < ry + rh
+            })
+            .map(|w| w.id)
+    }
+
+    pub fn hovered(&self) -> Option<Id> {
+        self.hover
+    }
+
+    /// Whether a widget is being pressed, in which case mouse moves belong
+    /// to it.
+    pub fn capturing(&self) -> bool {
+        self.pressed.is_some()
+    }
+
+    /// Move the mouse. Dragging a slider changes its value.
+    pub fn cursor_moved(&mut self, pos: (f32, f32)) -> Option<Event<Id>> {
+        self.hover = self.hit(pos);
+        let widget = self.get(self.pressed?)?;
+        match widget.kind {
+            Kind::Slider { index, .. } => {
+                let value = widget.value_at(pos.0)?;
+                (value != index).then_some(Event::Changed(widget.id, value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Press the mouse button. Pressing a slider moves it to the cursor.
+    pub fn press(&mut self, pos: (f32, f32)) -> Option<Event<Id>> {
+        self.pressed = self.hit(pos);
+        self.hover = self.pressed;
+        let widget = self.get(self.pressed?)?;
+        match widget.kind {
+            Kind::Slider { index, .. } => {
+                let value = widget.value_at(pos.0)?;
+                (value != index).then_some(Event::Changed(widget.id, value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Release the mouse button, which clicks the pressed widget if the
+    /// cursor is still over it.
+    pub fn release(&mut self, pos: (f32, f32)) -> Option<Event<Id>> {
+        let pressed = self.pressed.take()?;
+        if self.hit(pos) != Some(pressed) {
+            return None;
+        }
+        let widget = self.get(pressed)?;
+        match &widget.kind {
+            Kind::Button => Some(Event::Clicked(widget.id)),
+            Kind::Toggle(on) => Some(Event::Toggled(widget.id, !on)),
+            Kind::List { selected, .. } => {
+                let value = widget.value_at(pos.0)?;
+                (value != *selected).then_some(Event::Changed(widget.id, value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Move the keyboard focus by `delta` interactive widgets, wrapping
+    /// around.
+    pub fn focus(&mut self, delta: isize) {
+        let ids = self
+            .widgets
+            .iter()
+            .filter(|w| w.interactive())
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return;
+        }
+        self.focus = Some(
+            match self.focus.and_then(|f| ids.iter().position(|&id| id == f)) {
+                Some(i) => ids[(i as isize + delta).rem_euclid(ids.len() as isize) as usize],
+                None if delta < 0 => ids[ids.len() - 1],
+                None => ids[0],
+            },
+        );
+    }
+
+    /// Focus the first interactive widget, unless one already has focus.
+    pub fn ensure_focus(&mut self) {
+        if self.focus.is_none() {
+            self.focus(1);
+        }
+    }
+
+    pub fn clear_focus(&mut self) {
+        self.focus = None;
+    }
+
+    /// Activate the focused widget, as if clicked.
+    pub fn activate(&self) -> Option<Event<Id>> {
+        let widget = self.get(self.focus?)?;
+        match widget.kind {
+            Kind::Button => Some(Event::Clicked(widget.id)),
+            Kind::Toggle(on) => Some(Event::Toggled(widget.id, !on)),
+            _ => None,
+        }
+    }
+
+    /// Step the value of the focused slider, list or toggle.
+    pub fn step(&self, delta: isize) -> Option<Event<Id>> {
+        let widget = self.get(self.focus?)?;
+        match &widget.kind {
+            Kind::Slider { index, count } if *count > 0 => {
+                let value = (*index as isize + delta).clamp(0, *count as isize - 1) as usize;
+                (value != *index).then_some(Event::Changed(widget.id, value))
+            }
+            Kind::List { items, selected } if !items.is_empty() => {
+                let value = (*selected as isize + delta).rem_euclid(items.len() as isize);
+                Some(Event::Changed(widget.id, value as usize))
+            }
+            Kind::Toggle(on) => Some(Event::Toggled(widget.id, !on)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ui() -> Ui<u32> {
+        let mut ui = Ui::default();
+        ui.set(vec![
+            Widget::new(0, [0, 100, 100, 50], "Title", Kind::Label),
+            Widget::new(1, [0, 50, 100, 50], "OK", Kind::Button),
+            Widget::new(
+                2,
+                [0, 0, 100, 50],
+                "Speed",
+                Kind::Slider { index: 0, count: 5 },
+            ),
+            Widget::new(
+                3,
+                [100, 0, 100, 50],
+                "Theme",
+                Kind::List {
+                    items: vec!["a".into(), "b".into(), "c".into()],
+                    selected: 0,
+                },
+            ),
+        ]);
+        ui
+    }
+
+    #[test]
+    fn hit() {
+        let ui = ui();
+        assert_eq!(None, ui.hit((10.0, 120.0)));
+        assert_eq!(Some(1), ui.hit((10.0, 60.0)));
+        assert_eq!(Some(2), ui.hit((99.0, 0.0)));
+        assert_eq!(None, ui.hit((200.0, 0.0)));
+    }
+
+    #[test]
+    fn click() {
+        let mut ui = ui();
+        assert_eq!(None, ui.cursor_moved((10.0, 60.0)));
+        assert_eq!(None, ui.press((10.0, 60.0)));
+        let status = ui.widgets().find(|(w, _, _)| w.id == 1).unwrap().1;
+        assert_eq!(Status::Pressed, status);
+        assert_eq!(Some(Event::Clicked(1)), ui.release((20.0, 70.0)));
+
+        // releasing away from the button cancels the click
+        ui.press((10.0, 60.0));
+        assert_eq!(None, ui.release((10.0, 10.0)));
+    }
+
+    #[test]
+    fn drag_slider() {
+        let mut ui = ui();
+        // the control takes the right 60 pixels of the slider
+        assert_eq!(Some(Event::Changed(2, 2)), ui.press((70.0, 10.0)));
+        assert!(ui.capturing());
+        assert_eq!(Some(Event::Changed(2, 4)), ui.cursor_moved((150.0, 10.0)));
+        assert_eq!(None, ui.release((150.0, 10.0)));
+        assert!(!ui.capturing());
+    }
+
+    #[test]
+    fn select_list_item() {
+        let mut ui = ui();
+        ui.press((195.0, 10.0));
+        assert_eq!(Some(Event::Changed(3, 2)), ui.release((195.0, 10.0)));
+    }
+
+    #[test]
+    fn keyboard() {
+        let mut ui = ui();
+        assert_eq!(None, ui.activate());
+        ui.ensure_focus();
+        assert_eq!(Some(Event::Clicked(1)), ui.activate());
+
+        ui.focus(1);
+        assert_eq!(Some(Event::Changed(2, 1)), ui.step(1));
+        assert_eq!(None, ui.step(-1));
+
+        ui.focus(1);
+        assert_eq!(Some(Event::Changed(3, 2)), ui.step(-1));
+
+        // focus wraps around, skipping labels
+        ui.focus(1);
+        assert_eq!(Some(Event::Clicked(1)), ui.activate());
+    }
+
+    #[test]
+    fn empty_slider() {
+        let mut ui = Ui::default();
+        let slider = Kind::Slider { index: 0, count: 0 };
+        ui.set(vec![Widget::new(0, [0, 0, 100, 50], "Speed", slider)]);
+        ui.ensure_focus();
+        assert_eq!(None, ui.step(1));
+        assert_eq!(None, ui.press((80.0, 10.0)));
+    }
+
+    #[test]
+    fn toggle() {
+        let mut ui = Ui::default();
+        let toggle = |on| vec![Widget::new(0, [0, 0, 100, 50], "Sound", Kind::Toggle(on))];
+        ui.set(toggle(false));
+        assert_eq!(None, ui.press((80.0, 10.0)));
+        assert_eq!(Some(Event::Toggled(0, true)), ui.release((80.0, 10.0)));
+        ui.press((80.0, 10.0));
+        assert_eq!(None, ui.release((80.0, 100.0)));
+
+        // the app rebuilds the widget with its new state
+        ui.set(toggle(true));
+        ui.ensure_focus();
+        assert_eq!(Some(Event::Toggled(0, false)), ui.activate());
+        assert_eq!(Some(Event::Toggled(0, false)), ui.step(1));
+        assert_eq!(Some(Event::Toggled(0, false)), ui.step(-1));
+    }
+
+    #[test]
+    fn rebuild_keeps_focus() {
+        let mut ui = ui();
+        ui.ensure_focus();
+        ui.focus(1);
+        let mut widgets = ui.widgets.clone();
+        ui.set(widgets.clone());
+        assert_eq!(Some(2), ui.focus);
+
+        widgets.retain(|w| w.id != 2);
+        ui.set(widgets);
+        assert_eq!(None, ui.focus);
+    }
+}